@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+# Regenerates the self-signed CA, server and client certificates used by the `tls` tests.
+set -euo pipefail
+
+SCRIPT_DIR=$(cd -- "$(dirname -- "${BASH_SOURCE[0]}")" && pwd)
+CERT_DIR=$(cd -- "$SCRIPT_DIR/../tests/certs" && pwd)
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+
+cd "$CERT_DIR"
+openssl req -x509 -newkey rsa:2048 -nodes -days 36500 \
+  -keyout "$WORK_DIR/ca.key" -out ca.pem \
+  -subj "/CN=opensearch-sdk-rs test CA" \
+  -addext "basicConstraints=critical,CA:TRUE" \
+  -addext "keyUsage=critical,keyCertSign,cRLSign"
+
+printf "subjectAltName=DNS:localhost,IP:127.0.0.1\nbasicConstraints=CA:FALSE\nkeyUsage=digitalSignature,keyEncipherment\nextendedKeyUsage=serverAuth\n" \
+  > "$WORK_DIR/server.ext"
+printf "basicConstraints=CA:FALSE\nkeyUsage=digitalSignature,keyEncipherment\nextendedKeyUsage=clientAuth\n" \
+  > "$WORK_DIR/client.ext"
+
+for name in server client; do
+  openssl req -newkey rsa:2048 -nodes -keyout "$name.key" -out "$WORK_DIR/$name.csr" -subj "/CN=$name"
+  openssl x509 -req -days 36500 -in "$WORK_DIR/$name.csr" \
+    -CA ca.pem -CAkey "$WORK_DIR/ca.key" -CAcreateserial -CAserial "$WORK_DIR/ca.srl" \
+    -extfile "$WORK_DIR/$name.ext" -out "$name.pem"
+done
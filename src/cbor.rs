@@ -0,0 +1,295 @@
+use crate::json::JsonValue;
+use crate::xcontent::XContentError;
+use std::collections::BTreeMap;
+
+const MAX_DEPTH: usize = 128;
+const BREAK: u8 = 0xff;
+
+pub fn decode(input: &[u8]) -> Result<JsonValue, XContentError> {
+    let mut decoder = Decoder { input, position: 0 };
+    let value = decoder.read_value(0)?;
+    if decoder.position < input.len() {
+        return Err(decoder.error("unexpected trailing bytes"));
+    }
+    Ok(value)
+}
+
+pub fn encode(value: &JsonValue) -> Vec<u8> {
+    let mut output = Vec::new();
+    write_value(value, &mut output);
+    output
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Decoder<'_> {
+    fn error(&self, message: &str) -> XContentError {
+        XContentError::new(format!("{message} at offset {}", self.position))
+    }
+
+    fn next(&mut self) -> Result<u8, XContentError> {
+        let byte = *self
+            .input
+            .get(self.position)
+            .ok_or_else(|| self.error("unexpected end of CBOR input"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, length: u64) -> Result<&[u8], XContentError> {
+        let end = usize::try_from(length)
+            .ok()
+            .and_then(|length| self.position.checked_add(length))
+            .filter(|end| *end <= self.input.len())
+            .ok_or_else(|| self.error("unexpected end of CBOR input"))?;
+        let bytes = &self.input[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_uint(&mut self, additional: u8) -> Result<u64, XContentError> {
+        let width = match additional {
+            0..=23 => return Ok(additional.into()),
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            _ => return Err(self.error("invalid CBOR length")),
+        };
+        Ok(self
+            .take(width)?
+            .iter()
+            .fold(0, |value, byte| (value << 8) | u64::from(*byte)))
+    }
+
+    // Returns None for indefinite-length items.
+    fn read_length(&mut self, additional: u8) -> Result<Option<u64>, XContentError> {
+        if additional == 31 {
+            return Ok(None);
+        }
+        self.read_uint(additional).map(Some)
+    }
+
+    fn at_break(&mut self) -> bool {
+        if self.input.get(self.position) == Some(&BREAK) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    fn read_value(&mut self, depth: usize) -> Result<JsonValue, XContentError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("CBOR input is nested too deeply"));
+        }
+
+        let initial = self.next()?;
+        let (major, additional) = (initial >> 5, initial & 0x1f);
+        match major {
+            0 => Ok(integer(self.read_uint(additional)?, false)),
+            1 => Ok(integer(self.read_uint(additional)?, true)),
+            2 => Err(self.error("CBOR byte strings are not supported")),
+            3 => self.read_text(additional).map(JsonValue::String),
+            4 => {
+                let mut values = Vec::new();
+                match self.read_length(additional)? {
+                    Some(length) => {
+                        for _ in 0..length {
+                            values.push(self.read_value(depth + 1)?);
+                        }
+                    }
+                    None => {
+                        while !self.at_break() {
+                            values.push(self.read_value(depth + 1)?);
+                        }
+                    }
+                }
+                Ok(JsonValue::Array(values))
+            }
+            5 => {
+                let mut entries = BTreeMap::new();
+                let length = self.read_length(additional)?;
+                let mut read = 0;
+                loop {
+                    match length {
+                        Some(length) if read == length => break,
+                        None if self.at_break() => break,
+                        _ => {}
+                    }
+                    let key = match self.read_value(depth + 1)? {
+                        JsonValue::String(key) => key,
+                        JsonValue::Integer(key) => key.to_string(),
+                        _ => return Err(self.error("CBOR map keys must be strings")),
+                    };
+                    if entries.contains_key(&key) {
+                        return Err(self.error(&format!("duplicate map key [{key}]")));
+                    }
+                    entries.insert(key, self.read_value(depth + 1)?);
+                    read += 1;
+                }
+                Ok(JsonValue::Object(entries))
+            }
+            // Tags carry no meaning in the JSON model, so only the tagged value is kept.
+            6 => {
+                self.read_uint(additional)?;
+                self.read_value(depth + 1)
+            }
+            _ => self.read_simple(additional),
+        }
+    }
+
+    fn read_text(&mut self, additional: u8) -> Result<String, XContentError> {
+        let mut bytes = Vec::new();
+        match self.read_length(additional)? {
+            Some(length) => bytes.extend_from_slice(self.take(length)?),
+            None => {
+                while !self.at_break() {
+                    let initial = self.next()?;
+                    if initial >> 5 != 3 || initial & 0x1f == 31 {
+                        return Err(self.error("invalid CBOR text chunk"));
+                    }
+                    let length = self.read_uint(initial & 0x1f)?;
+                    bytes.extend_from_slice(self.take(length)?);
+                }
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| self.error("CBOR text is not valid UTF-8"))
+    }
+
+    fn read_simple(&mut self, additional: u8) -> Result<JsonValue, XContentError> {
+        match additional {
+            20 => Ok(JsonValue::Bool(false)),
+            21 => Ok(JsonValue::Bool(true)),
+            22 | 23 => Ok(JsonValue::Null),
+            25 => {
+                let bits = self.read_uint(additional)? as u16;
+                Ok(JsonValue::Float(half_to_f64(bits)))
+            }
+            26 => {
+                let bits = self.read_uint(additional)? as u32;
+                Ok(JsonValue::Float(f32::from_bits(bits).into()))
+            }
+            27 => Ok(JsonValue::Float(f64::from_bits(
+                self.read_uint(additional)?,
+            ))),
+            _ => Err(self.error("unsupported CBOR simple value")),
+        }
+    }
+}
+
+fn integer(value: u64, negative: bool) -> JsonValue {
+    match (i64::try_from(value), negative) {
+        (Ok(value), false) => JsonValue::Integer(value),
+        (Ok(value), true) => JsonValue::Integer(-1 - value),
+        (Err(_), false) => JsonValue::Float(value as f64),
+        (Err(_), true) => JsonValue::Float(-1.0 - value as f64),
+    }
+}
+
+fn half_to_f64(bits: u16) -> f64 {
+    let exponent = i32::from((bits >> 10) & 0x1f);
+    let mantissa = f64::from(bits & 0x3ff);
+    let magnitude = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (mantissa + 1024.0) * 2f64.powi(exponent - 25),
+    };
+    if bits & 0x8000 != 0 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+fn write_header(major: u8, value: u64, output: &mut Vec<u8>) {
+    let major = major << 5;
+    match value {
+        0..=23 => output.push(major | value as u8),
+        24..=0xff => output.extend_from_slice(&[major | 24, value as u8]),
+        0x100..=0xffff => {
+            output.push(major | 25);
+            output.extend_from_slice(&(value as u16).to_be_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            output.push(major | 26);
+            output.extend_from_slice(&(value as u32).to_be_bytes());
+        }
+        _ => {
+            output.push(major | 27);
+            output.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+fn write_value(value: &JsonValue, output: &mut Vec<u8>) {
+    match value {
+        JsonValue::Null => output.push(0xf6),
+        JsonValue::Bool(false) => output.push(0xf4),
+        JsonValue::Bool(true) => output.push(0xf5),
+        JsonValue::Integer(value) if *value >= 0 => write_header(0, *value as u64, output),
+        JsonValue::Integer(value) => write_header(1, (-1 - *value) as u64, output),
+        JsonValue::Float(value) => {
+            output.push(0xfb);
+            output.extend_from_slice(&value.to_bits().to_be_bytes());
+        }
+        JsonValue::String(value) => {
+            write_header(3, value.len() as u64, output);
+            output.extend_from_slice(value.as_bytes());
+        }
+        JsonValue::Array(values) => {
+            write_header(4, values.len() as u64, output);
+            for value in values {
+                write_value(value, output);
+            }
+        }
+        JsonValue::Object(entries) => {
+            write_header(5, entries.len() as u64, output);
+            for (key, value) in entries {
+                write_header(3, key.len() as u64, output);
+                output.extend_from_slice(key.as_bytes());
+                write_value(value, output);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::json::JsonValue;
+
+    #[test]
+    fn decodes_rfc_examples_and_round_trips() {
+        // {"a": 1, "b": [2, 3]} from RFC 8949 appendix A, plus indefinite-length forms.
+        let definite = [0xa2, 0x61, 0x61, 0x01, 0x61, 0x62, 0x82, 0x02, 0x03];
+        let indefinite = [
+            0xbf, 0x61, 0x61, 0x01, 0x61, 0x62, 0x9f, 0x02, 0x03, 0xff, 0xff,
+        ];
+        let expected = JsonValue::parse(r#"{"a":1,"b":[2,3]}"#).unwrap();
+        assert_eq!(decode(&definite).unwrap(), expected);
+        assert_eq!(decode(&indefinite).unwrap(), expected);
+        assert_eq!(encode(&expected), definite);
+
+        assert_eq!(
+            decode(&[0x39, 0x03, 0xe7]).unwrap(),
+            JsonValue::Integer(-1000)
+        );
+        assert_eq!(decode(&[0xf9, 0x3c, 0x00]).unwrap(), JsonValue::Float(1.0));
+        assert_eq!(decode(&[0xf9, 0xc4, 0x00]).unwrap(), JsonValue::Float(-4.0));
+
+        let document = JsonValue::parse(
+            r#"{"name":"é","ratio":0.25,"big":-9000000000,"ok":[true,false,null]}"#,
+        )
+        .unwrap();
+        assert_eq!(decode(&encode(&document)).unwrap(), document);
+        assert!(decode(&[0x82, 0x01]).is_err());
+
+        // {"a": 1, "a": 2}
+        let error = decode(&[0xa2, 0x61, 0x61, 0x01, 0x61, 0x61, 0x02]).unwrap_err();
+        assert_eq!(error.to_string(), "duplicate map key [a] at offset 6");
+    }
+}
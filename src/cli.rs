@@ -0,0 +1,440 @@
+use crate::extension::{Extension, ExtensionMetadata, Route, Router};
+use crate::host::{ExtensionHost, ShutdownHandle};
+use crate::log::{self, Level};
+use std::env;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, ErrorKind, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process;
+
+pub const CONFIG_ENV: &str = "OPENSEARCH_SDK_RS_CONFIG";
+
+const USAGE: &str = "\
+Usage: {program} [COMMAND] [OPTIONS]
+
+Commands:
+  serve            Listen for OpenSearch on the extension's transport port (default)
+  print-init-json  Print the body for `POST _extensions/initialize`
+  routes           List the registered REST routes and their action names
+  help             Print this message
+
+Options:
+  --config <PATH>  Load extension metadata from a JSON or YAML file [env: OPENSEARCH_SDK_RS_CONFIG]
+  --host <ADDR>    Override the address the extension listens on
+  --port <PORT>    Override the port the extension listens on
+  --trace          Log every transport frame (serve only)
+  -h, --help       Print this message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Serve,
+    PrintInitJson,
+    Routes,
+    Help,
+}
+
+impl Command {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "serve" => Some(Self::Serve),
+            "print-init-json" => Some(Self::PrintInitJson),
+            "routes" => Some(Self::Routes),
+            "help" => Some(Self::Help),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CliError {
+    pub message: String,
+}
+
+impl CliError {
+    fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for CliError {}
+
+// Metadata precedence, lowest first: the extension's own metadata, the `--config`
+// file, `OPENSEARCH_SDK_RS_*` environment overrides, then `--host` and `--port`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    pub config: Option<PathBuf>,
+    pub host: Option<IpAddr>,
+    pub port: Option<u16>,
+    pub trace: bool,
+}
+
+impl Cli {
+    // `args` excludes the program name.
+    pub fn parse<I, S>(args: I) -> Result<Self, CliError>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        let mut cli = Self {
+            command: Command::Serve,
+            config: None,
+            host: None,
+            port: None,
+            trace: false,
+        };
+        let mut args = args.into_iter().map(Into::into);
+        let mut command = None;
+
+        while let Some(arg) = args.next() {
+            let arg = arg.into_string().map_err(|arg| {
+                CliError::new(format!("invalid argument [{}]", arg.to_string_lossy()))
+            })?;
+            let mut value = |flag: &str| {
+                args.next()
+                    .ok_or_else(|| CliError::new(format!("{flag} requires a value")))
+            };
+            match arg.as_str() {
+                "-h" | "--help" => cli.command = Command::Help,
+                "--trace" => cli.trace = true,
+                "--config" => cli.config = Some(PathBuf::from(value("--config")?)),
+                "--host" => {
+                    let host = value("--host")?;
+                    cli.host = Some(host.to_string_lossy().parse().map_err(|_| {
+                        CliError::new(format!(
+                            "--host must be an IP address, got [{}]",
+                            host.to_string_lossy()
+                        ))
+                    })?);
+                }
+                "--port" => {
+                    let port = value("--port")?;
+                    cli.port = Some(port.to_string_lossy().parse().map_err(|_| {
+                        CliError::new(format!(
+                            "--port must be a port number between 0 and 65535, got [{}]",
+                            port.to_string_lossy()
+                        ))
+                    })?);
+                }
+                flag if flag.starts_with('-') => {
+                    return Err(CliError::new(format!("unknown option [{flag}]")));
+                }
+                name if command.is_none() => {
+                    command = Some(
+                        Command::parse(name)
+                            .ok_or_else(|| CliError::new(format!("unknown command [{name}]")))?,
+                    );
+                }
+                name => return Err(CliError::new(format!("unexpected argument [{name}]"))),
+            }
+        }
+
+        if cli.command != Command::Help {
+            cli.command = command.unwrap_or(Command::Serve);
+        }
+        if cli.trace && cli.command != Command::Serve {
+            return Err(CliError::new("--trace is only supported by `serve`"));
+        }
+        Ok(cli)
+    }
+
+    pub fn metadata(&self, defaults: &ExtensionMetadata) -> io::Result<ExtensionMetadata> {
+        let config = self
+            .config
+            .clone()
+            .or_else(|| env::var_os(CONFIG_ENV).map(PathBuf::from));
+        let mut metadata = match config {
+            Some(path) => ExtensionMetadata::from_file(path)?,
+            None => defaults.clone().with_env_overrides()?,
+        };
+        // A config file without a `tls` section keeps the extension's own settings.
+        metadata.tls = metadata.tls.or_else(|| defaults.tls.clone());
+        if let Some(host) = self.host {
+            metadata.host_address = host;
+        }
+        if let Some(port) = self.port {
+            metadata.port = port;
+        }
+        Ok(metadata)
+    }
+
+    pub fn run<E: Extension>(self, extension: E, output: &mut dyn Write) -> io::Result<()> {
+        self.run_with(extension, output, |host| host)
+    }
+
+    // Like `run`, but `configure` can add middleware, a JWT verifier, a worker pool
+    // or other host settings before `serve` starts listening.
+    pub fn run_with<E, F>(
+        self,
+        extension: E,
+        output: &mut dyn Write,
+        configure: F,
+    ) -> io::Result<()>
+    where
+        E: Extension,
+        F: FnOnce(ExtensionHost) -> ExtensionHost,
+    {
+        match self.command {
+            Command::Help => writeln!(output, "{}", usage()),
+            Command::PrintInitJson => {
+                let metadata = self.metadata(extension.metadata())?;
+                writeln!(output, "{}", metadata.to_value().to_pretty_string())
+            }
+            Command::Routes => {
+                let router = Router::new(extension.routes())
+                    .map_err(|error| io::Error::new(ErrorKind::InvalidInput, error))?;
+                for route in router.routes() {
+                    writeln!(output, "{}", route_line(route))?;
+                }
+                Ok(())
+            }
+            Command::Serve => {
+                if self.trace {
+                    log::set_max_level(Level::Trace);
+                }
+                let metadata = self.metadata(extension.metadata())?;
+                let host = configure(ExtensionHost::new(Configured {
+                    extension,
+                    metadata,
+                })?);
+                shutdown_on_signal(host.shutdown_handle());
+                host.serve()
+            }
+        }
+    }
+}
+
+// Entry point for extension binaries: parses the process arguments and runs the
+// chosen command against `extension`. Invalid arguments exit with status 2.
+pub fn run_cli<E: Extension>(extension: E) -> io::Result<()> {
+    run_cli_with(extension, |host| host)
+}
+
+// Like `run_cli`, with a hook to configure the host before it serves.
+pub fn run_cli_with<E, F>(extension: E, configure: F) -> io::Result<()>
+where
+    E: Extension,
+    F: FnOnce(ExtensionHost) -> ExtensionHost,
+{
+    match Cli::parse(env::args_os().skip(1)) {
+        Ok(cli) => cli.run_with(extension, &mut io::stdout().lock(), configure),
+        Err(error) => {
+            eprintln!("error: {error}\n\n{}", usage());
+            process::exit(2)
+        }
+    }
+}
+
+fn usage() -> String {
+    let program = env::args_os()
+        .next()
+        .map(PathBuf::from)
+        .and_then(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "server".into());
+    USAGE.replace("{program}", &program)
+}
+
+fn route_line(route: &Route) -> String {
+    let mut line = format!("{:<7} {} {}", route.method.as_str(), route.path, route.name);
+    if route.deprecation.is_some() {
+        line.push_str(" (deprecated)");
+    }
+    if let Some((method, path)) = &route.replaces {
+        line.push_str(&format!(" (replaces {} {path})", method.as_str()));
+    }
+    line
+}
+
+// Serves `extension` under metadata resolved from the command line.
+struct Configured<E> {
+    extension: E,
+    metadata: ExtensionMetadata,
+}
+
+impl<E: Extension> Extension for Configured<E> {
+    fn metadata(&self) -> &ExtensionMetadata {
+        &self.metadata
+    }
+
+    fn routes(&self) -> Vec<Route> {
+        self.extension.routes()
+    }
+
+    fn implemented_interfaces(&self) -> Vec<String> {
+        self.extension.implemented_interfaces()
+    }
+
+    fn on_start(&self) {
+        self.extension.on_start();
+    }
+
+    fn on_initialized(&self) {
+        self.extension.on_initialized();
+    }
+
+    fn on_shutdown(&self) {
+        self.extension.on_shutdown();
+    }
+}
+
+// Requests a graceful shutdown on SIGINT or SIGTERM.
+#[cfg(unix)]
+pub fn shutdown_on_signal(handle: ShutdownHandle) {
+    signals::shutdown_on_signal(handle);
+}
+
+#[cfg(not(unix))]
+pub fn shutdown_on_signal(_handle: ShutdownHandle) {}
+
+#[cfg(unix)]
+mod signals {
+    use crate::host::ShutdownHandle;
+    use libc::{c_int, sighandler_t, SIGINT, SIGTERM};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_signal(_signum: c_int) {
+        RECEIVED.store(true, Ordering::SeqCst);
+    }
+
+    pub fn shutdown_on_signal(handle: ShutdownHandle) {
+        let handler = on_signal as extern "C" fn(c_int) as sighandler_t;
+        // SAFETY: `on_signal` only stores to an atomic, which is async-signal-safe.
+        unsafe {
+            libc::signal(SIGINT, handler);
+            libc::signal(SIGTERM, handler);
+        }
+
+        thread::spawn(move || {
+            while !RECEIVED.load(Ordering::SeqCst) {
+                thread::sleep(Duration::from_millis(100));
+            }
+            handle.shutdown();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command};
+    use crate::extension::{Extension, ExtensionMetadata, Route};
+    use crate::json::JsonValue;
+    use crate::rest::{ExtensionRestResponse, RestMethod, RestStatus};
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    struct CliExtension {
+        metadata: ExtensionMetadata,
+    }
+
+    impl Extension for CliExtension {
+        fn metadata(&self) -> &ExtensionMetadata {
+            &self.metadata
+        }
+
+        fn routes(&self) -> Vec<Route> {
+            let ok = |_request| ExtensionRestResponse::text(RestStatus::Ok, "ok");
+            vec![
+                Route::new(RestMethod::Get, "/hello", "cli:hello", ok),
+                Route::new(RestMethod::Delete, "/hello/{id}", "cli:delete", ok)
+                    .deprecated("use DELETE /items/{id}"),
+            ]
+        }
+    }
+
+    fn run(args: &[&str]) -> String {
+        let extension = CliExtension {
+            metadata: ExtensionMetadata::new("CLI", "cli-rs"),
+        };
+        let mut output = Vec::new();
+        Cli::parse(args.iter().copied())
+            .unwrap()
+            .run(extension, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn arguments_select_a_command_and_overrides() {
+        let cli = Cli::parse(["--port", "9301", "serve", "--trace", "--host", "0.0.0.0"]).unwrap();
+        assert_eq!(cli.command, Command::Serve);
+        assert_eq!(cli.port, Some(9301));
+        assert_eq!(cli.host, Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)));
+        assert!(cli.trace);
+
+        assert_eq!(
+            Cli::parse(Vec::<String>::new()).unwrap().command,
+            Command::Serve
+        );
+        assert_eq!(
+            Cli::parse(["routes", "--help"]).unwrap().command,
+            Command::Help
+        );
+        assert_eq!(
+            Cli::parse(["--port", "http"]).unwrap_err().message,
+            "--port must be a port number between 0 and 65535, got [http]"
+        );
+        assert_eq!(
+            Cli::parse(["--host"]).unwrap_err().message,
+            "--host requires a value"
+        );
+        assert_eq!(
+            Cli::parse(["stop"]).unwrap_err().message,
+            "unknown command [stop]"
+        );
+        assert!(Cli::parse(["routes", "--trace"]).is_err());
+    }
+
+    #[test]
+    fn print_init_json_and_routes_describe_the_extension() {
+        let json = JsonValue::parse(&run(&["print-init-json", "--port", "4321"])).unwrap();
+        assert_eq!(
+            json.get("uniqueId").and_then(JsonValue::as_str),
+            Some("cli-rs")
+        );
+        assert_eq!(json.get("port").and_then(JsonValue::as_str), Some("4321"));
+        assert_eq!(ExtensionMetadata::from_value(&json).unwrap().port, 4321);
+
+        assert_eq!(
+            run(&["routes"]),
+            "GET     /hello cli:hello\nDELETE  /hello/{id} cli:delete (deprecated)\n"
+        );
+    }
+
+    #[test]
+    fn serve_lets_the_caller_configure_the_host() {
+        let extension = CliExtension {
+            metadata: ExtensionMetadata::new("CLI", "cli-rs"),
+        };
+        let configured = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&configured);
+        Cli::parse(["serve", "--host", "127.0.0.1", "--port", "0"])
+            .unwrap()
+            .run_with(extension, &mut Vec::new(), move |host| {
+                flag.store(true, Ordering::SeqCst);
+                // Stop before accepting so `serve` returns straight away.
+                host.shutdown_handle().shutdown();
+                host.with_shutdown_timeout(Duration::ZERO)
+            })
+            .unwrap();
+        assert!(configured.load(Ordering::SeqCst));
+    }
+}
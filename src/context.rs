@@ -0,0 +1,221 @@
+use crate::error::ExtensionError;
+use crate::transport::ThreadContext;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+
+// The host installs the `ThreadContext` of the transport request for as long as a
+// REST handler runs on the current thread, mirroring OpenSearch's thread-local
+// `ThreadContext`. Response headers added here travel back on the transport response.
+thread_local! {
+    static CURRENT: RefCell<ThreadContext> = RefCell::new(ThreadContext::default());
+}
+
+pub fn current() -> ThreadContext {
+    CURRENT.with(|context| context.borrow().clone())
+}
+
+// Header names are matched case-insensitively, like `HeaderMap`.
+pub fn request_header(name: &str) -> Option<String> {
+    CURRENT.with(|context| {
+        context
+            .borrow()
+            .request_headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    })
+}
+
+pub fn put_header(name: impl Into<String>, value: impl Into<String>) -> Result<(), ExtensionError> {
+    let name = name.into();
+    CURRENT.with(|context| {
+        let headers = &mut context.borrow_mut().request_headers;
+        if headers.keys().any(|key| key.eq_ignore_ascii_case(&name)) {
+            return Err(ExtensionError::bad_request(format!(
+                "request header [{name}] is already present"
+            )));
+        }
+        headers.insert(name, value.into());
+        Ok(())
+    })
+}
+
+pub fn transient_header(name: &str) -> Option<String> {
+    CURRENT.with(|context| context.borrow().transient_headers.get(name).cloned())
+}
+
+pub fn put_transient(
+    name: impl Into<String>,
+    value: impl Into<String>,
+) -> Result<(), ExtensionError> {
+    let name = name.into();
+    CURRENT.with(|context| {
+        let headers = &mut context.borrow_mut().transient_headers;
+        if headers.contains_key(&name) {
+            return Err(ExtensionError::bad_request(format!(
+                "transient header [{name}] is already present"
+            )));
+        }
+        headers.insert(name, value.into());
+        Ok(())
+    })
+}
+
+pub fn add_response_header(name: impl Into<String>, value: impl Into<String>) {
+    CURRENT.with(|context| {
+        context
+            .borrow_mut()
+            .response_headers
+            .entry(name.into())
+            .or_default()
+            .insert(value.into());
+    });
+}
+
+pub fn response_headers() -> BTreeMap<String, BTreeSet<String>> {
+    CURRENT.with(|context| context.borrow().response_headers.clone())
+}
+
+// The context a request sent from this thread carries; `host::send_request` attaches
+// it to every request a handler sends.
+pub fn outbound() -> ThreadContext {
+    CURRENT.with(|context| context.borrow().to_outbound())
+}
+
+// Replaces the current context with an empty one until the returned guard is
+// restored or dropped, like Java's `ThreadContext.stashContext()`.
+pub fn stash_context() -> StoredContext {
+    StoredContext::replace(ThreadContext::default(), false)
+}
+
+// Stashes the current context and starts a new one holding its request headers plus
+// `headers`. Headers already in the context win over the ones passed in.
+pub fn stash_and_merge_headers(headers: &BTreeMap<String, String>) -> StoredContext {
+    let mut merged = ThreadContext {
+        request_headers: headers.clone(),
+        ..ThreadContext::default()
+    };
+    merged.request_headers.extend(current().request_headers);
+    StoredContext::replace(merged, false)
+}
+
+// Keeps a copy of the current context to return to later. With
+// `preserve_response_headers`, response headers added in the meantime survive
+// the restore.
+pub fn new_stored_context(preserve_response_headers: bool) -> StoredContext {
+    StoredContext::replace(current(), preserve_response_headers)
+}
+
+// Runs `call` with `context` installed and returns the context as `call` left it.
+pub(crate) fn scope<T>(context: ThreadContext, call: impl FnOnce() -> T) -> (T, ThreadContext) {
+    let stored = StoredContext::replace(context, false);
+    let value = call();
+    let context = current();
+    stored.restore();
+    (value, context)
+}
+
+#[must_use = "the stored context is restored as soon as it is dropped"]
+pub struct StoredContext {
+    stored: Option<ThreadContext>,
+    preserve_response_headers: bool,
+}
+
+impl StoredContext {
+    fn replace(context: ThreadContext, preserve_response_headers: bool) -> Self {
+        let stored = CURRENT.with(|current| current.replace(context));
+        Self {
+            stored: Some(stored),
+            preserve_response_headers,
+        }
+    }
+
+    pub fn restore(mut self) {
+        self.restore_stored();
+    }
+
+    fn restore_stored(&mut self) {
+        let Some(mut stored) = self.stored.take() else {
+            return;
+        };
+        CURRENT.with(|current| {
+            let replaced = current.take();
+            if self.preserve_response_headers {
+                for (name, values) in replaced.response_headers {
+                    stored
+                        .response_headers
+                        .entry(name)
+                        .or_default()
+                        .extend(values);
+                }
+            }
+            current.replace(stored);
+        });
+    }
+}
+
+impl Drop for StoredContext {
+    fn drop(&mut self) {
+        self.restore_stored();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_response_header, current, new_stored_context, outbound, put_header, put_transient,
+        request_header, response_headers, scope, stash_and_merge_headers, stash_context,
+        transient_header,
+    };
+    use crate::transport::ThreadContext;
+    use std::collections::BTreeMap;
+
+    fn request_context() -> ThreadContext {
+        ThreadContext {
+            request_headers: BTreeMap::from([("X-Opaque-Id".to_string(), "trace-1".to_string())]),
+            ..ThreadContext::default()
+        }
+    }
+
+    #[test]
+    fn stashed_contexts_are_restored_when_dropped() {
+        let ((), context) = scope(request_context(), || {
+            assert!(put_header("x-opaque-id", "other").is_err());
+            assert_eq!(request_header("x-opaque-id").as_deref(), Some("trace-1"));
+            put_transient("user", "admin").unwrap();
+            add_response_header("Warning", "299 deprecated");
+
+            {
+                let _stored = stash_context();
+                assert_eq!(request_header("X-Opaque-Id"), None);
+                assert_eq!(transient_header("user"), None);
+                put_header("X-Opaque-Id", "stashed").unwrap();
+            }
+            assert_eq!(request_header("X-Opaque-Id").as_deref(), Some("trace-1"));
+            assert_eq!(transient_header("user").as_deref(), Some("admin"));
+
+            let merged = stash_and_merge_headers(&BTreeMap::from([
+                ("X-Opaque-Id".to_string(), "ignored".to_string()),
+                ("security".to_string(), "token".to_string()),
+            ]));
+            assert_eq!(request_header("X-Opaque-Id").as_deref(), Some("trace-1"));
+            assert_eq!(request_header("security").as_deref(), Some("token"));
+            assert!(response_headers().is_empty());
+            merged.restore();
+
+            let preserved = new_stored_context(true);
+            add_response_header("Warning", "299 second");
+            drop(preserved);
+            assert_eq!(response_headers()["Warning"].len(), 2);
+
+            let outbound = outbound();
+            assert_eq!(outbound.request_headers, request_context().request_headers);
+            assert!(outbound.response_headers.is_empty());
+            assert!(outbound.transient_headers.is_empty());
+        });
+
+        assert_eq!(context.request_headers, request_context().request_headers);
+        assert_eq!(context.response_headers["Warning"].len(), 2);
+        assert_eq!(current(), ThreadContext::default());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::json::JsonValue;
+use crate::rest::RestStatus;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionError {
+    pub error_type: String,
+    pub reason: String,
+    pub status: RestStatus,
+    pub cause: Option<Box<ExtensionError>>,
+}
+
+impl ExtensionError {
+    pub fn new(
+        status: RestStatus,
+        error_type: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            error_type: error_type.into(),
+            reason: reason.into(),
+            status,
+            cause: None,
+        }
+    }
+
+    pub fn bad_request(reason: impl Into<String>) -> Self {
+        Self::new(RestStatus::BadRequest, "illegal_argument_exception", reason)
+    }
+
+    pub fn parse(reason: impl Into<String>) -> Self {
+        Self::new(RestStatus::BadRequest, "parse_exception", reason)
+    }
+
+    pub fn unauthorized(reason: impl Into<String>) -> Self {
+        Self::new(RestStatus::Unauthorized, "security_exception", reason)
+    }
+
+    pub fn not_found(reason: impl Into<String>) -> Self {
+        Self::new(RestStatus::NotFound, "resource_not_found_exception", reason)
+    }
+
+    pub fn method_not_allowed(reason: impl Into<String>) -> Self {
+        Self::new(
+            RestStatus::MethodNotAllowed,
+            "method_not_allowed_exception",
+            reason,
+        )
+    }
+
+    pub fn not_acceptable(reason: impl Into<String>) -> Self {
+        Self::new(
+            RestStatus::NotAcceptable,
+            "media_type_header_exception",
+            reason,
+        )
+    }
+
+    pub fn content_too_long(reason: impl Into<String>) -> Self {
+        Self::new(
+            RestStatus::RequestEntityTooLarge,
+            "content_too_long_exception",
+            reason,
+        )
+    }
+
+    pub fn rejected(reason: impl Into<String>) -> Self {
+        Self::new(
+            RestStatus::TooManyRequests,
+            "rejected_execution_exception",
+            reason,
+        )
+    }
+
+    pub fn internal(reason: impl Into<String>) -> Self {
+        Self::new(RestStatus::InternalServerError, "exception", reason)
+    }
+
+    pub fn with_cause(mut self, cause: ExtensionError) -> Self {
+        self.cause = Some(Box::new(cause));
+        self
+    }
+
+    pub fn root_cause(&self) -> &ExtensionError {
+        let mut current = self;
+        while let Some(cause) = &current.cause {
+            current = cause;
+        }
+        current
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut error = self.cause_json();
+        if let JsonValue::Object(entries) = &mut error {
+            entries.insert(
+                "root_cause".into(),
+                JsonValue::Array(vec![self.root_cause().summary_json()]),
+            );
+        }
+
+        JsonValue::object([
+            ("error", error),
+            ("status", JsonValue::from(u32::from(self.status.code()))),
+        ])
+    }
+
+    fn summary_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("type", JsonValue::from(self.error_type.as_str())),
+            ("reason", JsonValue::from(self.reason.as_str())),
+        ])
+    }
+
+    fn cause_json(&self) -> JsonValue {
+        let mut json = self.summary_json();
+        if let (JsonValue::Object(entries), Some(cause)) = (&mut json, &self.cause) {
+            entries.insert("caused_by".into(), cause.cause_json());
+        }
+        json
+    }
+}
+
+impl fmt::Display for ExtensionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "[{}] {}", self.error_type, self.reason)
+    }
+}
+
+impl std::error::Error for ExtensionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn std::error::Error + 'static))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExtensionError;
+    use crate::rest::RestStatus;
+
+    #[test]
+    fn error_renders_opensearch_shape_with_cause_chain() {
+        let error = ExtensionError::new(
+            RestStatus::Conflict,
+            "version_conflict_engine_exception",
+            "[item][1]: version conflict",
+        )
+        .with_cause(ExtensionError::parse("unexpected token"));
+
+        assert_eq!(
+            error.to_json().to_string(),
+            concat!(
+                r#"{"error":{"caused_by":{"reason":"unexpected token","type":"parse_exception"},"#,
+                r#""reason":"[item][1]: version conflict","root_cause":[{"reason":"unexpected token","type":"parse_exception"}],"#,
+                r#""type":"version_conflict_engine_exception"},"status":409}"#
+            )
+        );
+        assert_eq!(error.root_cause().error_type, "parse_exception");
+        assert_eq!(
+            error.to_string(),
+            "[version_conflict_engine_exception] [item][1]: version conflict"
+        );
+    }
+}
@@ -1,6 +1,19 @@
-use crate::rest::{ExtensionRestRequest, ExtensionRestResponse, RestMethod, RestStatus};
+use crate::error::ExtensionError;
+use crate::extract::Handler;
+use crate::header;
+use crate::json::JsonValue;
+use crate::middleware::{Middleware, MiddlewareStack};
+use crate::rest::{
+    decode_component, ExtensionRestRequest, ExtensionRestResponse, IntoRestResponse, RestMethod,
+    RestStatus,
+};
+use crate::tls::{ClientAuth, TlsConfig};
+use crate::xcontent::XContentType;
+use std::collections::{BTreeMap, BTreeSet};
 use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::{env, fmt, fs, io};
 
 pub type RouteHandler = Arc<dyn Fn(ExtensionRestRequest) -> ExtensionRestResponse + Send + Sync>;
 
@@ -13,6 +26,8 @@ pub struct ExtensionMetadata {
     pub version: String,
     pub opensearch_version: String,
     pub minimum_compatible_version: String,
+    pub dependencies: Vec<Dependency>,
+    pub tls: Option<TlsConfig>,
 }
 
 impl ExtensionMetadata {
@@ -25,8 +40,354 @@ impl ExtensionMetadata {
             version: "0.1.0".into(),
             opensearch_version: "3.6.0".into(),
             minimum_compatible_version: "2.19.0".into(),
+            dependencies: Vec::new(),
+            tls: None,
         }
     }
+
+    // Variables checked by `with_env_overrides`, with the field each one replaces.
+    pub const ENV_OVERRIDES: [(&'static str, &'static str); 3] = [
+        ("OPENSEARCH_SDK_RS_UNIQUE_ID", "uniqueId"),
+        ("OPENSEARCH_SDK_RS_HOST", "hostAddress"),
+        ("OPENSEARCH_SDK_RS_PORT", "port"),
+    ];
+
+    // Loads the extension descriptor OpenSearch registers through
+    // `POST _extensions/initialize`, as JSON or, for `.yml`/`.yaml` files, YAML.
+    // Environment overrides are applied on top of the file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, MetadataError> {
+        let path = path.as_ref();
+        let content = fs::read(path).map_err(|error| MetadataError::Read {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        })?;
+        let xcontent = match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension)
+                if extension.eq_ignore_ascii_case("yml")
+                    || extension.eq_ignore_ascii_case("yaml") =>
+            {
+                XContentType::Yaml
+            }
+            _ => XContentType::Json,
+        };
+        let value = xcontent
+            .decode(&content)
+            .map_err(|error| MetadataError::Parse {
+                path: path.to_path_buf(),
+                message: error.message,
+            })?;
+        Self::from_value(&value)?.with_env_overrides()
+    }
+
+    // `name` and `uniqueId` are required; every other field keeps the default from
+    // `new` when absent. Unknown fields are ignored.
+    pub fn from_value(value: &JsonValue) -> Result<Self, MetadataError> {
+        let object = value
+            .as_object()
+            .ok_or_else(|| MetadataError::InvalidField {
+                field: String::new(),
+                reason: "expected an object".into(),
+            })?;
+        let required = |field: &str| {
+            object
+                .get(field)
+                .ok_or_else(|| MetadataError::invalid(field, "is required"))
+                .and_then(|value| non_empty_string(field, value))
+        };
+
+        let mut metadata = Self::new(required("name")?, required("uniqueId")?);
+        for field in [
+            "hostAddress",
+            "port",
+            "version",
+            "opensearchVersion",
+            "minimumCompatibleVersion",
+        ] {
+            if let Some(value) = object.get(field) {
+                metadata.set_field(field, field, value)?;
+            }
+        }
+
+        if let Some(dependencies) = object.get("dependencies") {
+            let dependencies = dependencies
+                .as_array()
+                .ok_or_else(|| MetadataError::invalid("dependencies", "must be an array"))?;
+            for (index, dependency) in dependencies.iter().enumerate() {
+                metadata
+                    .dependencies
+                    .push(Dependency::from_value(index, dependency)?);
+            }
+        }
+        if let Some(tls) = object.get("tls") {
+            metadata.tls = Some(tls_from_value(tls)?);
+        }
+        Ok(metadata)
+    }
+
+    // The body OpenSearch expects on `POST _extensions/initialize`, in the same shape
+    // `from_value` reads. TLS settings stay local to the extension and are left out.
+    pub fn to_value(&self) -> JsonValue {
+        let mut fields = BTreeMap::from([
+            ("name".to_string(), JsonValue::from(self.name.as_str())),
+            ("uniqueId".into(), JsonValue::from(self.unique_id.as_str())),
+            (
+                "hostAddress".into(),
+                JsonValue::from(self.host_address.to_string()),
+            ),
+            ("port".into(), JsonValue::from(self.port.to_string())),
+            ("version".into(), JsonValue::from(self.version.as_str())),
+            (
+                "opensearchVersion".into(),
+                JsonValue::from(self.opensearch_version.as_str()),
+            ),
+            (
+                "minimumCompatibleVersion".into(),
+                JsonValue::from(self.minimum_compatible_version.as_str()),
+            ),
+        ]);
+        if !self.dependencies.is_empty() {
+            let dependencies = self
+                .dependencies
+                .iter()
+                .map(|dependency| {
+                    JsonValue::object([
+                        ("uniqueId", JsonValue::from(dependency.unique_id.as_str())),
+                        ("version", JsonValue::from(dependency.version.as_str())),
+                    ])
+                })
+                .collect();
+            fields.insert("dependencies".into(), JsonValue::Array(dependencies));
+        }
+        JsonValue::Object(fields)
+    }
+
+    pub fn with_env_overrides(self) -> Result<Self, MetadataError> {
+        self.with_overrides(|name| env::var(name).ok())
+    }
+
+    fn with_overrides(
+        mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, MetadataError> {
+        for (variable, field) in Self::ENV_OVERRIDES {
+            if let Some(value) = lookup(variable) {
+                self.set_field(variable, field, &JsonValue::String(value))?;
+            }
+        }
+        Ok(self)
+    }
+
+    // `source` names the offending config key or environment variable in errors.
+    fn set_field(
+        &mut self,
+        source: &str,
+        field: &str,
+        value: &JsonValue,
+    ) -> Result<(), MetadataError> {
+        match field {
+            "uniqueId" => self.unique_id = non_empty_string(source, value)?,
+            "hostAddress" => {
+                self.host_address = non_empty_string(source, value)?
+                    .parse()
+                    .map_err(|_| MetadataError::invalid(source, "must be an IP address"))?;
+            }
+            "port" => {
+                let port = match value {
+                    JsonValue::Integer(port) => Some(*port),
+                    JsonValue::String(port) => port.trim().parse().ok(),
+                    _ => None,
+                };
+                self.port = port
+                    .and_then(|port| u16::try_from(port).ok())
+                    .ok_or_else(|| {
+                        MetadataError::invalid(source, "must be a port number between 0 and 65535")
+                    })?;
+            }
+            "version" => self.version = non_empty_string(source, value)?,
+            "opensearchVersion" => self.opensearch_version = non_empty_string(source, value)?,
+            "minimumCompatibleVersion" => {
+                self.minimum_compatible_version = non_empty_string(source, value)?;
+            }
+            _ => unreachable!("unknown metadata field [{field}]"),
+        }
+        Ok(())
+    }
+
+    pub fn with_dependency(mut self, dependency: Dependency) -> Self {
+        self.dependencies.push(dependency);
+        self
+    }
+
+    pub fn with_tls(mut self, tls: TlsConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+}
+
+// Another extension this one needs, checked by OpenSearch when it loads the
+// extension. `version` is the minimum compatible version of the dependency.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    pub unique_id: String,
+    pub version: String,
+}
+
+impl Dependency {
+    pub fn new(unique_id: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            unique_id: unique_id.into(),
+            version: version.into(),
+        }
+    }
+
+    fn from_value(index: usize, value: &JsonValue) -> Result<Self, MetadataError> {
+        let field = |name: &str| format!("dependencies[{index}].{name}");
+        let object = value.as_object().ok_or_else(|| {
+            MetadataError::invalid(&format!("dependencies[{index}]"), "must be an object")
+        })?;
+        let string = |name: &str| {
+            let field = field(name);
+            object
+                .get(name)
+                .ok_or_else(|| MetadataError::invalid(&field, "is required"))
+                .and_then(|value| non_empty_string(&field, value))
+        };
+        Ok(Self::new(string("uniqueId")?, string("version")?))
+    }
+}
+
+// Reads the `tls` section of a config file; paths are used as written.
+fn tls_from_value(value: &JsonValue) -> Result<TlsConfig, MetadataError> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| MetadataError::invalid("tls", "must be an object"))?;
+    let path = |name: &str| {
+        object
+            .get(name)
+            .map(|value| non_empty_string(&format!("tls.{name}"), value).map(PathBuf::from))
+            .transpose()
+    };
+    let required = |name: &str| {
+        path(name)?.ok_or_else(|| MetadataError::invalid(&format!("tls.{name}"), "is required"))
+    };
+
+    let mut tls = TlsConfig::new(required("certificatePath")?, required("privateKeyPath")?);
+    tls.ca_path = path("caPath")?;
+    if let Some(client_auth) = object.get("clientAuth") {
+        tls.client_auth = client_auth
+            .as_str()
+            .and_then(ClientAuth::parse)
+            .ok_or_else(|| {
+                MetadataError::invalid(
+                    "tls.clientAuth",
+                    "must be one of [none], [optional] or [required]",
+                )
+            })?;
+    }
+    if let Some(verify_hostname) = object.get("verifyHostname") {
+        tls.verify_hostname = verify_hostname
+            .as_bool()
+            .ok_or_else(|| MetadataError::invalid("tls.verifyHostname", "must be a boolean"))?;
+    }
+    match (
+        path("clientCertificatePath")?,
+        path("clientPrivateKeyPath")?,
+    ) {
+        (Some(certificate_path), Some(private_key_path)) => {
+            tls = tls.with_client_certificate(certificate_path, private_key_path);
+        }
+        (None, None) => {}
+        (Some(_), None) => {
+            return Err(MetadataError::invalid(
+                "tls.clientPrivateKeyPath",
+                "is required with [tls.clientCertificatePath]",
+            ))
+        }
+        (None, Some(_)) => {
+            return Err(MetadataError::invalid(
+                "tls.clientCertificatePath",
+                "is required with [tls.clientPrivateKeyPath]",
+            ))
+        }
+    }
+    Ok(tls)
+}
+
+fn non_empty_string(field: &str, value: &JsonValue) -> Result<String, MetadataError> {
+    match value.as_str().map(str::trim) {
+        Some(value) if !value.is_empty() => Ok(value.to_string()),
+        _ => Err(MetadataError::invalid(field, "must be a non-empty string")),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+    Read { path: PathBuf, message: String },
+    Parse { path: PathBuf, message: String },
+    InvalidField { field: String, reason: String },
+}
+
+impl MetadataError {
+    fn invalid(field: &str, reason: &str) -> Self {
+        Self::InvalidField {
+            field: field.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Read { path, message } => {
+                write!(formatter, "failed to read [{}]: {message}", path.display())
+            }
+            Self::Parse { path, message } => {
+                write!(formatter, "failed to parse [{}]: {message}", path.display())
+            }
+            Self::InvalidField { field, reason } if field.is_empty() => {
+                write!(formatter, "invalid extension metadata: {reason}")
+            }
+            Self::InvalidField { field, reason } => write!(formatter, "[{field}] {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+impl From<MetadataError> for io::Error {
+    fn from(error: MetadataError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ActionName(String);
+
+impl ActionName {
+    // Mirrors org.opensearch.rest.NamedRoute#isValidRouteName.
+    pub const MAX_LENGTH: usize = 250;
+
+    pub fn new(name: impl Into<String>) -> Result<Self, RouterError> {
+        let name = name.into();
+        let valid_characters = name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || ":/*_".contains(character));
+        if name.trim().is_empty() || name.len() > Self::MAX_LENGTH || !valid_characters {
+            return Err(RouterError::InvalidActionName { name });
+        }
+        Ok(Self(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ActionName {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
 }
 
 #[derive(Clone)]
@@ -34,27 +395,142 @@ pub struct Route {
     pub method: RestMethod,
     pub path: String,
     pub name: String,
+    pub permission: Option<String>,
+    pub description: Option<String>,
+    pub deprecation: Option<String>,
+    pub replaces: Option<(RestMethod, String)>,
+    pub params: Option<BTreeSet<String>>,
     pub handler: RouteHandler,
+    pub middleware: MiddlewareStack,
 }
 
 impl Route {
-    pub fn new<F>(
+    pub fn new<F, R>(
         method: RestMethod,
         path: impl Into<String>,
         name: impl Into<String>,
         handler: F,
     ) -> Self
     where
-        F: Fn(ExtensionRestRequest) -> ExtensionRestResponse + Send + Sync + 'static,
+        F: Fn(ExtensionRestRequest) -> R + Send + Sync + 'static,
+        R: IntoRestResponse,
+    {
+        Self::handler::<F, ExtensionRestRequest>(method, path, name, handler)
+    }
+
+    // Handlers taking extractors such as `Path`, `Query` or `Json` instead of the request.
+    pub fn handler<H, Args>(
+        method: RestMethod,
+        path: impl Into<String>,
+        name: impl Into<String>,
+        handler: H,
+    ) -> Self
+    where
+        H: Handler<Args>,
     {
         Self {
             method,
             path: path.into(),
             name: name.into(),
-            handler: Arc::new(handler),
+            permission: None,
+            description: None,
+            deprecation: None,
+            replaces: None,
+            params: None,
+            handler: Arc::new(move |request| handler.handle(request)),
+            middleware: Vec::new(),
         }
     }
 
+    pub fn with_permission(mut self, permission: impl Into<String>) -> Self {
+        self.permission = Some(permission.into());
+        self
+    }
+
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn deprecated(mut self, message: impl Into<String>) -> Self {
+        self.deprecation = Some(message.into());
+        self
+    }
+
+    pub fn replaces(mut self, old_method: RestMethod, old_path: impl Into<String>) -> Self {
+        self.replaces = Some((old_method, old_path.into()));
+        self
+    }
+
+    // Declared params are checked before the handler runs, like OpenSearch rejects
+    // unknown params before executing the action. Undeclared routes are checked
+    // against what the handler read once it returns.
+    pub fn with_params<I, S>(mut self, params: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.params = Some(params.into_iter().map(Into::into).collect());
+        self
+    }
+
+    // The old endpoint keeps serving through this handler, flagged the way
+    // OpenSearch's RestController flags replaced routes.
+    pub fn replaced_route(&self) -> Option<Route> {
+        let (old_method, old_path) = self.replaces.as_ref()?;
+        let mut replaced = self.clone();
+        replaced.method = *old_method;
+        replaced.path = old_path.clone();
+        replaced.replaces = None;
+        replaced.deprecation = Some(format!(
+            "[{} {}] is deprecated! Use [{} {}] instead.",
+            old_method.as_str(),
+            old_path,
+            self.method.as_str(),
+            self.path
+        ));
+        Some(replaced)
+    }
+
+    pub fn deprecated_registration_string(&self) -> Option<String> {
+        let message = self.deprecation.as_ref()?;
+        Some(format!(
+            "{} {} {}",
+            self.method.as_str(),
+            self.path,
+            message
+        ))
+    }
+
+    pub fn action_name(&self) -> Result<ActionName, RouterError> {
+        ActionName::new(self.name.as_str())
+    }
+
+    pub fn manifest_entry(&self) -> JsonValue {
+        let mut entry = JsonValue::object([
+            ("method", JsonValue::from(self.method.as_str())),
+            ("path", JsonValue::from(self.path.as_str())),
+            ("name", JsonValue::from(self.name.as_str())),
+        ]);
+        if let JsonValue::Object(fields) = &mut entry {
+            if let Some(permission) = &self.permission {
+                fields.insert("permission".into(), JsonValue::from(permission.as_str()));
+            }
+            if let Some(description) = &self.description {
+                fields.insert("description".into(), JsonValue::from(description.as_str()));
+            }
+            if let Some(deprecation) = &self.deprecation {
+                fields.insert("deprecated".into(), JsonValue::from(deprecation.as_str()));
+            }
+        }
+        entry
+    }
+
+    pub fn with_middleware<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
     pub fn registration_string(&self) -> String {
         format!("{} {} {}", self.method.as_str(), self.path, self.name)
     }
@@ -64,27 +540,274 @@ impl Route {
     }
 
     pub fn matches(&self, method: RestMethod, path: &str) -> bool {
-        if self.method != method {
-            return false;
-        }
+        self.method == method && self.matches_path(path)
+    }
 
-        let expected = self.path.split('/').filter(|segment| !segment.is_empty());
-        let actual = path.split('/').filter(|segment| !segment.is_empty());
+    pub fn matches_path(&self, path: &str) -> bool {
+        self.match_path(path).is_some()
+    }
 
-        let expected = expected.collect::<Vec<_>>();
-        let actual = actual.collect::<Vec<_>>();
+    pub fn match_path(&self, path: &str) -> Option<BTreeMap<String, String>> {
+        let expected = path_segments(&self.path).collect::<Vec<_>>();
+        let actual = path_segments(path).collect::<Vec<_>>();
 
         if expected.len() != actual.len() {
-            return false;
+            return None;
+        }
+
+        let mut params = BTreeMap::new();
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            if let Some(name) = wildcard_name(expected) {
+                params.insert(name.to_string(), decode_component(actual, true));
+            } else if expected != actual {
+                return None;
+            }
+        }
+
+        Some(params)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouterError {
+    DuplicateRoute {
+        route_key: String,
+    },
+    ConflictingWildcard {
+        route_key: String,
+        existing: String,
+        conflicting: String,
+    },
+    InvalidPath {
+        route_key: String,
+        reason: String,
+    },
+    InvalidActionName {
+        name: String,
+    },
+    DuplicateActionName {
+        name: String,
+    },
+}
+
+impl fmt::Display for RouterError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateRoute { route_key } => {
+                write!(formatter, "route [{route_key}] is registered more than once")
+            }
+            Self::ConflictingWildcard {
+                route_key,
+                existing,
+                conflicting,
+            } => write!(
+                formatter,
+                "route [{route_key}] uses wildcard [{{{conflicting}}}] where another route already uses [{{{existing}}}]"
+            ),
+            Self::InvalidPath { route_key, reason } => {
+                write!(formatter, "route [{route_key}] has an invalid path: {reason}")
+            }
+            Self::InvalidActionName { name } => write!(
+                formatter,
+                "invalid route name [{name}]: the route name may include the characters 'a-z', 'A-Z', '0-9', ':', '/', '*', '_' and be at most {} characters",
+                ActionName::MAX_LENGTH
+            ),
+            Self::DuplicateActionName { name } => {
+                write!(formatter, "route name [{name}] is registered more than once")
+            }
         }
+    }
+}
+
+impl std::error::Error for RouterError {}
+
+#[derive(Clone, Default)]
+struct RouterNode {
+    literals: BTreeMap<String, RouterNode>,
+    wildcard: Option<(String, Box<RouterNode>)>,
+    routes: BTreeMap<RestMethod, Route>,
+}
+
+#[derive(Clone, Default)]
+pub struct Router {
+    root: RouterNode,
+    routes: Vec<Route>,
+}
+
+pub struct RouteMatch<'a> {
+    pub routes: &'a BTreeMap<RestMethod, Route>,
+    pub params: BTreeMap<String, String>,
+}
+
+impl RouteMatch<'_> {
+    pub fn route(&self, method: RestMethod) -> Option<&Route> {
+        self.routes.get(&method)
+    }
+}
 
-        expected
+impl Router {
+    pub fn new(routes: impl IntoIterator<Item = Route>) -> Result<Self, RouterError> {
+        let mut router = Self::default();
+        for route in routes {
+            router.insert(route)?;
+        }
+        Ok(router)
+    }
+
+    pub fn insert(&mut self, route: Route) -> Result<(), RouterError> {
+        route.action_name()?;
+        if self
+            .routes
             .iter()
-            .zip(actual.iter())
-            .all(|(expected, actual)| {
-                (expected.starts_with('{') && expected.ends_with('}')) || expected == actual
+            .any(|existing| existing.name == route.name)
+        {
+            return Err(RouterError::DuplicateActionName { name: route.name });
+        }
+
+        self.insert_node(route.clone())?;
+        if let Some(replaced) = route.replaced_route() {
+            self.insert_node(replaced)?;
+        }
+        self.routes.push(route);
+        Ok(())
+    }
+
+    fn insert_node(&mut self, route: Route) -> Result<(), RouterError> {
+        let route_key = route.route_key();
+        let mut node = &mut self.root;
+        for segment in path_segments(&route.path) {
+            node = match wildcard_name(segment) {
+                Some("") => {
+                    return Err(RouterError::InvalidPath {
+                        route_key,
+                        reason: "wildcard segments must be named".into(),
+                    })
+                }
+                Some(name) => {
+                    let (existing, child) = node
+                        .wildcard
+                        .get_or_insert_with(|| (name.to_string(), Box::default()));
+                    if existing != name {
+                        return Err(RouterError::ConflictingWildcard {
+                            route_key,
+                            existing: existing.clone(),
+                            conflicting: name.to_string(),
+                        });
+                    }
+                    child
+                }
+                None => node.literals.entry(segment.to_string()).or_default(),
+            };
+        }
+
+        if node.routes.contains_key(&route.method) {
+            return Err(RouterError::DuplicateRoute { route_key });
+        }
+        node.routes.insert(route.method, route);
+        Ok(())
+    }
+
+    pub fn find(&self, path: &str) -> Option<RouteMatch<'_>> {
+        self.find_all(path).into_iter().next()
+    }
+
+    // Every node matching `path`, most specific first: at each segment the literal
+    // child is tried before the wildcard, like OpenSearch's PathTrie. A trie node can
+    // only be reached one way for a given path, so no node is visited twice.
+    pub fn find_all(&self, path: &str) -> Vec<RouteMatch<'_>> {
+        let segments = path_segments(path).collect::<Vec<_>>();
+        let mut matches = Vec::new();
+        collect_matches(&self.root, &segments, &mut Vec::new(), &mut matches);
+        matches
+    }
+
+    // The most specific match with a route for `method`; less specific matches are
+    // tried when a more specific path only registers other methods.
+    pub fn find_route(
+        &self,
+        method: RestMethod,
+        path: &str,
+    ) -> Option<(&Route, BTreeMap<String, String>)> {
+        self.find_all(path).into_iter().find_map(|matched| {
+            let route = matched.routes.get(&method)?;
+            Some((route, matched.params))
+        })
+    }
+
+    pub fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    pub fn manifest(&self) -> JsonValue {
+        JsonValue::Array(self.routes.iter().map(Route::manifest_entry).collect())
+    }
+
+    pub fn rest_actions(&self) -> Vec<String> {
+        self.routes
+            .iter()
+            .filter(|route| route.deprecation.is_none())
+            .map(Route::registration_string)
+            .collect()
+    }
+
+    pub fn deprecated_rest_actions(&self) -> Vec<String> {
+        self.routes
+            .iter()
+            .flat_map(|route| {
+                let replaced = route
+                    .replaced_route()
+                    .and_then(|replaced| replaced.deprecated_registration_string());
+                route
+                    .deprecated_registration_string()
+                    .into_iter()
+                    .chain(replaced)
             })
+            .collect()
+    }
+}
+
+fn collect_matches<'a, 'p>(
+    node: &'a RouterNode,
+    segments: &[&'p str],
+    captured: &mut Vec<(&'a str, &'p str)>,
+    matches: &mut Vec<RouteMatch<'a>>,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        if !node.routes.is_empty() {
+            matches.push(RouteMatch {
+                routes: &node.routes,
+                params: captured
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), decode_component(value, true)))
+                    .collect(),
+            });
+        }
+        return;
+    };
+
+    if let Some(child) = node.literals.get(*segment) {
+        collect_matches(child, rest, captured, matches);
     }
+    if let Some((name, child)) = &node.wildcard {
+        captured.push((name, segment));
+        collect_matches(child, rest, captured, matches);
+        captured.pop();
+    }
+}
+
+fn path_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+pub(crate) fn path_param_names(path: &str) -> Vec<String> {
+    path_segments(path)
+        .filter_map(wildcard_name)
+        .map(str::to_string)
+        .collect()
+}
+
+fn wildcard_name(segment: &str) -> Option<&str> {
+    segment.strip_prefix('{')?.strip_suffix('}')
 }
 
 pub trait Extension: Send + Sync + 'static {
@@ -98,24 +821,94 @@ pub trait Extension: Send + Sync + 'static {
             vec!["ActionExtension".into()]
         }
     }
+
+    fn on_start(&self) {}
+
+    fn on_initialized(&self) {}
+
+    fn on_shutdown(&self) {}
 }
 
 pub fn not_found_response(request: ExtensionRestRequest) -> ExtensionRestResponse {
-    ExtensionRestResponse::from_request(
+    let error = ExtensionError::not_found(format!(
+        "no handler found for uri [{}] and method [{}]",
+        request.uri,
+        request.method.as_str()
+    ));
+    ExtensionRestResponse::error_from_request(request, &error)
+}
+
+pub fn allowed_methods<'a>(routes: impl IntoIterator<Item = &'a Route>) -> Vec<RestMethod> {
+    let mut methods = routes
+        .into_iter()
+        .map(|route| route.method)
+        .collect::<BTreeSet<_>>();
+    if methods.contains(&RestMethod::Get) {
+        methods.insert(RestMethod::Head);
+    }
+    methods.insert(RestMethod::Options);
+    methods.into_iter().collect()
+}
+
+pub fn method_not_allowed_response(
+    request: ExtensionRestRequest,
+    allowed: &[RestMethod],
+) -> ExtensionRestResponse {
+    let error = ExtensionError::method_not_allowed(format!(
+        "Incorrect HTTP method for uri [{}] and method [{}], allowed: [{}]",
+        request.uri,
+        request.method.as_str(),
+        allowed
+            .iter()
+            .map(|method| method.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    let mut response = ExtensionRestResponse::error_from_request(request, &error);
+    response
+        .headers
+        .insert(header::ALLOW, allow_header(allowed));
+    response
+}
+
+pub fn options_response(
+    request: ExtensionRestRequest,
+    allowed: &[RestMethod],
+) -> ExtensionRestResponse {
+    let mut response = ExtensionRestResponse::from_request(
         request,
-        RestStatus::NotFound,
+        RestStatus::Ok,
         ExtensionRestResponse::TEXT_CONTENT_TYPE,
-        b"No handler for route".to_vec(),
-    )
+        Vec::new(),
+    );
+    response
+        .headers
+        .insert(header::ALLOW, allow_header(allowed));
+    response
+}
+
+fn allow_header(allowed: &[RestMethod]) -> String {
+    allowed
+        .iter()
+        .map(|method| method.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Route;
+    use super::{
+        ActionName, Dependency, ExtensionMetadata, MetadataError, Route, Router, RouterError,
+    };
+    use crate::header::HeaderMap;
+    use crate::json::JsonValue;
     use crate::rest::{
         ExtensionRestRequest, ExtensionRestResponse, HttpVersion, RestMethod, RestStatus,
     };
+    use crate::tls::{ClientAuth, TlsConfig};
     use std::collections::BTreeMap;
+    use std::fs;
+    use std::net::{IpAddr, Ipv4Addr};
 
     fn request(path: &str) -> ExtensionRestRequest {
         ExtensionRestRequest::new(
@@ -123,7 +916,7 @@ mod tests {
             path.into(),
             path.into(),
             BTreeMap::new(),
-            BTreeMap::new(),
+            HeaderMap::new(),
             None,
             Vec::new(),
             String::new(),
@@ -149,4 +942,306 @@ mod tests {
         let response = (route.handler)(request("/hello/world"));
         assert_eq!(response.status, RestStatus::Ok);
     }
+
+    #[test]
+    fn route_captures_decoded_path_params() {
+        let route = Route::new(
+            RestMethod::Get,
+            "/indices/{index}/docs/{id}",
+            "test:doc",
+            |request| ExtensionRestResponse::text(RestStatus::Ok, request.path),
+        );
+
+        let params = route.match_path("/indices/logs%2D2024/docs/42").unwrap();
+        assert_eq!(params.get("index").map(String::as_str), Some("logs-2024"));
+        assert_eq!(params.get("id").map(String::as_str), Some("42"));
+        assert!(route.match_path("/indices/logs/docs").is_none());
+
+        let params = route.match_path("/indices/a+b/docs/c%2Bd").unwrap();
+        assert_eq!(params.get("index").map(String::as_str), Some("a+b"));
+        assert_eq!(params.get("id").map(String::as_str), Some("c+d"));
+        assert_eq!(crate::rest::decode_component("a+b%20c", false), "a b c");
+    }
+
+    fn named_route(method: RestMethod, path: &str, name: &str) -> Route {
+        Route::new(method, path, name, |request| {
+            ExtensionRestResponse::text(RestStatus::Ok, request.path)
+        })
+    }
+
+    #[test]
+    fn router_prefers_literals_over_wildcards_regardless_of_order() {
+        let router = Router::new(vec![
+            named_route(RestMethod::Get, "/items/{id}", "test:get"),
+            named_route(RestMethod::Get, "/items/_search", "test:search"),
+            named_route(RestMethod::Get, "/items/{id}/versions", "test:versions"),
+            named_route(
+                RestMethod::Get,
+                "/items/_search/{scroll}/detail",
+                "test:scroll",
+            ),
+        ])
+        .unwrap();
+
+        let search = router.find("/items/_search").unwrap();
+        assert_eq!(search.route(RestMethod::Get).unwrap().name, "test:search");
+        assert!(search.params.is_empty());
+
+        let item = router.find("/items/42").unwrap();
+        assert_eq!(item.route(RestMethod::Get).unwrap().name, "test:get");
+        assert_eq!(item.params.get("id").map(String::as_str), Some("42"));
+
+        let versions = router.find("/items/_search/versions").unwrap();
+        assert_eq!(
+            versions.route(RestMethod::Get).unwrap().name,
+            "test:versions"
+        );
+        assert_eq!(
+            versions.params.get("id").map(String::as_str),
+            Some("_search")
+        );
+
+        assert!(router.find("/items").is_none());
+        assert!(router.find("/items/42/other").is_none());
+    }
+
+    #[test]
+    fn methods_fall_through_to_less_specific_paths() {
+        let router = Router::new(vec![
+            named_route(RestMethod::Get, "/items/_search", "test:search"),
+            named_route(RestMethod::Delete, "/items/{id}", "test:delete"),
+        ])
+        .unwrap();
+
+        assert_eq!(router.find_all("/items/_search").len(), 2);
+        let (route, params) = router
+            .find_route(RestMethod::Delete, "/items/_search")
+            .unwrap();
+        assert_eq!(route.name, "test:delete");
+        assert_eq!(params.get("id").map(String::as_str), Some("_search"));
+        let (route, params) = router
+            .find_route(RestMethod::Get, "/items/_search")
+            .unwrap();
+        assert_eq!(route.name, "test:search");
+        assert!(params.is_empty());
+        assert!(router
+            .find_route(RestMethod::Put, "/items/_search")
+            .is_none());
+    }
+
+    #[test]
+    fn router_rejects_duplicate_and_ambiguous_routes() {
+        let duplicate = Router::new(vec![
+            named_route(RestMethod::Get, "/items/{id}", "test:first"),
+            named_route(RestMethod::Get, "/items/{id}", "test:second"),
+        ]);
+        assert_eq!(
+            duplicate.err(),
+            Some(RouterError::DuplicateRoute {
+                route_key: "GET /items/{id}".into()
+            })
+        );
+
+        let ambiguous = Router::new(vec![
+            named_route(RestMethod::Get, "/items/{id}", "test:get"),
+            named_route(RestMethod::Delete, "/items/{name}", "test:delete"),
+        ]);
+        assert_eq!(
+            ambiguous.err().unwrap().to_string(),
+            "route [DELETE /items/{name}] uses wildcard [{name}] where another route already uses [{id}]"
+        );
+
+        assert!(Router::new(vec![
+            named_route(RestMethod::Get, "/items/{id}", "test:get"),
+            named_route(RestMethod::Delete, "/items/{id}", "test:delete"),
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn action_names_follow_opensearch_rules_and_stay_unique() {
+        assert_eq!(
+            ActionName::new("hello_world_rs:hello/*").unwrap().as_str(),
+            "hello_world_rs:hello/*"
+        );
+        assert!(ActionName::new("").is_err());
+        assert!(ActionName::new("hello world").is_err());
+        assert!(ActionName::new("a".repeat(ActionName::MAX_LENGTH + 1)).is_err());
+
+        let invalid = Router::new(vec![named_route(
+            RestMethod::Get,
+            "/items",
+            "test:get-items",
+        )]);
+        assert_eq!(
+            invalid.err(),
+            Some(RouterError::InvalidActionName {
+                name: "test:get-items".into()
+            })
+        );
+
+        let duplicate = Router::new(vec![
+            named_route(RestMethod::Get, "/items", "test:items"),
+            named_route(RestMethod::Post, "/items", "test:items"),
+        ]);
+        assert_eq!(
+            duplicate.err().unwrap().to_string(),
+            "route name [test:items] is registered more than once"
+        );
+    }
+
+    #[test]
+    fn manifest_lists_routes_with_permissions() {
+        let router = Router::new(vec![
+            named_route(RestMethod::Get, "/items/{id}", "test:get")
+                .with_permission("cluster:admin/test/items/get")
+                .with_description("Fetch one item"),
+            named_route(RestMethod::Delete, "/items/{id}", "test:delete"),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            router.manifest(),
+            JsonValue::parse(
+                r#"[
+                    {"method":"GET","path":"/items/{id}","name":"test:get",
+                     "permission":"cluster:admin/test/items/get","description":"Fetch one item"},
+                    {"method":"DELETE","path":"/items/{id}","name":"test:delete"}
+                ]"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn deprecated_and_replaced_routes_register_in_the_deprecated_list() {
+        let router = Router::new(vec![
+            named_route(RestMethod::Get, "/_items/{id}", "test:get")
+                .replaces(RestMethod::Get, "/items/{id}"),
+            named_route(RestMethod::Post, "/items/_legacy", "test:legacy")
+                .deprecated("legacy search is deprecated"),
+        ])
+        .unwrap();
+
+        assert_eq!(router.rest_actions(), vec!["GET /_items/{id} test:get"]);
+        assert_eq!(
+            router.deprecated_rest_actions(),
+            vec![
+                "GET /items/{id} [GET /items/{id}] is deprecated! Use [GET /_items/{id}] instead.",
+                "POST /items/_legacy legacy search is deprecated"
+            ]
+        );
+
+        let old = router.find("/items/7").unwrap();
+        let replaced = old.route(RestMethod::Get).unwrap();
+        assert_eq!(replaced.name, "test:get");
+        assert!(replaced.deprecation.is_some());
+        assert!(router
+            .find("/_items/7")
+            .unwrap()
+            .route(RestMethod::Get)
+            .unwrap()
+            .deprecation
+            .is_none());
+    }
+
+    #[test]
+    fn metadata_loads_from_json_and_yaml_files() {
+        let hello = JsonValue::parse(include_str!("../examples/hello/hello.json")).unwrap();
+        let metadata = ExtensionMetadata::from_value(&hello).unwrap();
+        assert_eq!(
+            metadata,
+            ExtensionMetadata::new("Hello World", "hello-world-rs")
+        );
+        assert_eq!(metadata.to_value(), hello);
+
+        let path = std::env::temp_dir().join(format!("extension-{}.yml", std::process::id()));
+        fs::write(
+            &path,
+            "name: Search Relevance\nuniqueId: search-relevance\nhostAddress: 10.0.0.5\nport: 4532\n\
+             dependencies:\n  - uniqueId: hello-world-rs\n    version: 0.1.0\n",
+        )
+        .unwrap();
+        let metadata = ExtensionMetadata::from_file(&path);
+        fs::remove_file(&path).unwrap();
+        let metadata = metadata.unwrap();
+        assert_eq!(metadata.unique_id, "search-relevance");
+        assert_eq!(
+            metadata.host_address,
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 5))
+        );
+        assert_eq!(metadata.port, 4532);
+        assert_eq!(metadata.opensearch_version, "3.6.0");
+        assert_eq!(
+            metadata.dependencies,
+            vec![Dependency::new("hello-world-rs", "0.1.0")]
+        );
+        assert_eq!(
+            ExtensionMetadata::from_value(&metadata.to_value()).unwrap(),
+            metadata
+        );
+
+        let tls = JsonValue::parse(
+            r#"{"name":"x","uniqueId":"x","tls":{"certificatePath":"server.pem",
+            "privateKeyPath":"server.key","caPath":"ca.pem","clientAuth":"required",
+            "verifyHostname":false,"clientCertificatePath":"client.pem",
+            "clientPrivateKeyPath":"client.key"}}"#,
+        )
+        .unwrap();
+        let metadata = ExtensionMetadata::from_value(&tls).unwrap();
+        assert_eq!(
+            metadata.tls,
+            Some(
+                TlsConfig::new("server.pem", "server.key")
+                    .with_ca_path("ca.pem")
+                    .with_client_auth(ClientAuth::Required)
+                    .with_hostname_verification(false)
+                    .with_client_certificate("client.pem", "client.key")
+            )
+        );
+        assert!(metadata.to_value().get("tls").is_none());
+    }
+
+    #[test]
+    fn metadata_errors_name_the_offending_field() {
+        let error = |json: &str| {
+            ExtensionMetadata::from_value(&JsonValue::parse(json).unwrap())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(error(r#"{"name":"x"}"#), "[uniqueId] is required");
+        assert_eq!(
+            error(r#"{"name":"x","uniqueId":"x","port":"70000"}"#),
+            "[port] must be a port number between 0 and 65535"
+        );
+        assert_eq!(
+            error(r#"{"name":"x","uniqueId":"x","dependencies":[{"uniqueId":"y"}]}"#),
+            "[dependencies[0].version] is required"
+        );
+        assert_eq!(
+            error(r#"{"name":"x","uniqueId":"x","tls":{"certificatePath":"a.pem"}}"#),
+            "[tls.privateKeyPath] is required"
+        );
+        assert_eq!(
+            error(
+                r#"{"name":"x","uniqueId":"x","tls":{"certificatePath":"a.pem","privateKeyPath":"a.key","clientAuth":"maybe"}}"#
+            ),
+            "[tls.clientAuth] must be one of [none], [optional] or [required]"
+        );
+
+        let metadata = ExtensionMetadata::new("x", "x")
+            .with_overrides(|name| (name == "OPENSEARCH_SDK_RS_PORT").then(|| "9000".into()))
+            .unwrap();
+        assert_eq!(metadata.port, 9000);
+        let error = ExtensionMetadata::new("x", "x")
+            .with_overrides(|name| (name == "OPENSEARCH_SDK_RS_HOST").then(|| "nowhere".into()))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            MetadataError::InvalidField {
+                field: "OPENSEARCH_SDK_RS_HOST".into(),
+                reason: "must be an IP address".into(),
+            }
+        );
+    }
 }
@@ -0,0 +1,615 @@
+use crate::context;
+use crate::error::ExtensionError;
+use crate::extension::path_param_names;
+use crate::header;
+use crate::identity::Identity;
+use crate::json::JsonValue;
+use crate::rest::{ExtensionRestRequest, ExtensionRestResponse, IntoRestResponse, RequestUsage};
+use crate::transport::ThreadContext;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+pub trait FromRequest: Sized {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError>;
+
+    // `Option<Self>` only becomes None when the value is absent; malformed values
+    // still reject the request.
+    fn from_optional_request(
+        request: &mut ExtensionRestRequest,
+    ) -> Result<Option<Self>, ExtensionError> {
+        Self::from_request(request).map(Some)
+    }
+}
+
+impl<T: FromRequest> FromRequest for Option<T> {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        T::from_optional_request(request)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<T>(pub T);
+
+pub trait FromPathParams: Sized {
+    fn from_path_params(
+        names: &[String],
+        request: &ExtensionRestRequest,
+    ) -> Result<Self, ExtensionError>;
+}
+
+impl<T: FromPathParams> FromRequest for Path<T> {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        let names = path_param_names(&request.route_path);
+        T::from_path_params(&names, request).map(Path)
+    }
+}
+
+impl FromPathParams for BTreeMap<String, String> {
+    fn from_path_params(
+        _names: &[String],
+        request: &ExtensionRestRequest,
+    ) -> Result<Self, ExtensionError> {
+        Ok(request.path_params.clone())
+    }
+}
+
+fn path_param_at<T>(
+    names: &[String],
+    index: usize,
+    request: &ExtensionRestRequest,
+) -> Result<T, ExtensionError>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match names.get(index) {
+        Some(name) => request.path_param_as(name),
+        None => Err(ExtensionError::internal(format!(
+            "route [{}] has {} path parameters but the handler expects more",
+            request.route_path,
+            names.len()
+        ))),
+    }
+}
+
+macro_rules! impl_from_path_params {
+    ($($index:tt => $param:ident),+) => {
+        impl<$($param),+> FromPathParams for ($($param,)+)
+        where
+            $($param: FromStr, $param::Err: Display,)+
+        {
+            fn from_path_params(
+                names: &[String],
+                request: &ExtensionRestRequest,
+            ) -> Result<Self, ExtensionError> {
+                Ok(($(path_param_at::<$param>(names, $index, request)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_path_params!(0 => A);
+impl_from_path_params!(0 => A, 1 => B);
+impl_from_path_params!(0 => A, 1 => B, 2 => C);
+impl_from_path_params!(0 => A, 1 => B, 2 => C, 3 => D);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<T>(pub T);
+
+pub trait FromQuery: Sized {
+    fn from_query(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError>;
+}
+
+impl<T: FromQuery> FromRequest for Query<T> {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        T::from_query(request).map(Query)
+    }
+}
+
+#[cfg(not(feature = "serde"))]
+impl FromQuery for BTreeMap<String, String> {
+    fn from_query(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        for key in request.params.keys() {
+            request.usage.consume_param(key);
+        }
+        Ok(request.params.clone())
+    }
+}
+
+// Structs only consume their own fields, so unknown params are still rejected;
+// maps such as `BTreeMap<String, String>` take every param.
+#[cfg(feature = "serde")]
+impl<T: serde::de::DeserializeOwned> FromQuery for T {
+    fn from_query(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        let params = query::Params {
+            params: &request.params,
+            usage: &request.usage,
+        };
+        T::deserialize(params).map_err(|error| {
+            ExtensionError::bad_request(format!("invalid query parameters: {error}"))
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod query {
+    use crate::rest::RequestUsage;
+    use serde::de::value::Error;
+    use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+    use serde::forward_to_deserialize_any;
+    use std::collections::BTreeMap;
+    use std::vec;
+
+    pub(super) struct Params<'a> {
+        pub(super) params: &'a BTreeMap<String, String>,
+        pub(super) usage: &'a RequestUsage,
+    }
+
+    impl<'a> Params<'a> {
+        fn entries(self, keep: impl Fn(&str) -> bool) -> Entries<'a> {
+            Entries {
+                entries: self
+                    .params
+                    .iter()
+                    .filter(|(key, _)| keep(key))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+                value: None,
+                usage: self.usage,
+            }
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Params<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            self.deserialize_map(visitor)
+        }
+
+        fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_map(self.entries(|_| true))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_map(self.entries(|key| fields.contains(&key)))
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct enum
+            identifier ignored_any
+        }
+    }
+
+    struct Entries<'a> {
+        entries: vec::IntoIter<(&'a String, &'a String)>,
+        value: Option<&'a str>,
+        usage: &'a RequestUsage,
+    }
+
+    impl<'de> MapAccess<'de> for Entries<'_> {
+        type Error = Error;
+
+        fn next_key_seed<K: DeserializeSeed<'de>>(
+            &mut self,
+            seed: K,
+        ) -> Result<Option<K::Value>, Error> {
+            let Some((key, value)) = self.entries.next() else {
+                return Ok(None);
+            };
+            self.usage.consume_param(key);
+            self.value = Some(value);
+            seed.deserialize(key.as_str().into_deserializer()).map(Some)
+        }
+
+        fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+            match self.value.take() {
+                Some(value) => seed.deserialize(Value(value)),
+                None => Err(de::Error::custom("value requested before key")),
+            }
+        }
+    }
+
+    // Param values are strings on the wire; scalars are parsed on request.
+    struct Value<'a>(&'a str);
+
+    macro_rules! parse_value {
+        ($($method:ident => $visit:ident),+) => {
+            $(
+                fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                    match self.0.parse() {
+                        Ok(value) => visitor.$visit(value),
+                        Err(_) => Err(de::Error::invalid_value(
+                            de::Unexpected::Str(self.0),
+                            &visitor,
+                        )),
+                    }
+                }
+            )+
+        };
+    }
+
+    impl<'de> de::Deserializer<'de> for Value<'_> {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_str(self.0)
+        }
+
+        parse_value!(
+            deserialize_bool => visit_bool,
+            deserialize_i8 => visit_i8,
+            deserialize_i16 => visit_i16,
+            deserialize_i32 => visit_i32,
+            deserialize_i64 => visit_i64,
+            deserialize_u8 => visit_u8,
+            deserialize_u16 => visit_u16,
+            deserialize_u32 => visit_u32,
+            deserialize_u64 => visit_u64,
+            deserialize_f32 => visit_f32,
+            deserialize_f64 => visit_f64,
+            deserialize_char => visit_char
+        );
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            visitor.visit_some(self)
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            visitor.visit_enum(self.0.into_deserializer())
+        }
+
+        forward_to_deserialize_any! {
+            i128 u128 str string bytes byte_buf unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct identifier ignored_any
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json<T>(pub T);
+
+pub trait FromJsonBody: Sized {
+    fn from_json_body(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError>;
+}
+
+impl<T: FromJsonBody> FromRequest for Json<T> {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        T::from_json_body(request).map(Json)
+    }
+
+    fn from_optional_request(
+        request: &mut ExtensionRestRequest,
+    ) -> Result<Option<Self>, ExtensionError> {
+        if !request.has_content() {
+            return Ok(None);
+        }
+        Self::from_request(request).map(Some)
+    }
+}
+
+impl FromJsonBody for JsonValue {
+    fn from_json_body(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        request.json_body()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::de::DeserializeOwned> FromJsonBody for T {
+    fn from_json_body(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        request.parse_body()
+    }
+}
+
+pub trait HeaderName {
+    const NAME: &'static str;
+}
+
+pub struct XOpaqueId;
+
+impl HeaderName for XOpaqueId {
+    const NAME: &'static str = header::X_OPAQUE_ID;
+}
+
+pub struct Header<N: HeaderName> {
+    pub value: String,
+    name: PhantomData<N>,
+}
+
+impl<N: HeaderName> Header<N> {
+    pub fn new(value: impl Into<String>) -> Self {
+        Self {
+            value: value.into(),
+            name: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        N::NAME
+    }
+}
+
+impl<N: HeaderName> FromRequest for Header<N> {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        request.header(N::NAME).map(Header::new).ok_or_else(|| {
+            ExtensionError::bad_request(format!("missing required header [{}]", N::NAME))
+        })
+    }
+
+    fn from_optional_request(
+        request: &mut ExtensionRestRequest,
+    ) -> Result<Option<Self>, ExtensionError> {
+        Ok(request.header(N::NAME).map(Header::new))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal(pub String);
+
+impl FromRequest for Principal {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        if request.principal_identifier_token.is_empty() {
+            return Err(ExtensionError::bad_request(
+                "request does not carry a principal identifier token",
+            ));
+        }
+        Ok(Principal(request.principal_identifier_token.clone()))
+    }
+
+    fn from_optional_request(
+        request: &mut ExtensionRestRequest,
+    ) -> Result<Option<Self>, ExtensionError> {
+        if request.principal_identifier_token.is_empty() {
+            return Ok(None);
+        }
+        Self::from_request(request).map(Some)
+    }
+}
+
+impl FromRequest for ThreadContext {
+    fn from_request(_request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        Ok(context::current())
+    }
+}
+
+impl FromRequest for Identity {
+    fn from_request(request: &mut ExtensionRestRequest) -> Result<Self, ExtensionError> {
+        Ok(request.identity())
+    }
+}
+
+pub trait Handler<Args>: Send + Sync + 'static {
+    fn handle(&self, request: ExtensionRestRequest) -> ExtensionRestResponse;
+}
+
+impl<F, R> Handler<ExtensionRestRequest> for F
+where
+    F: Fn(ExtensionRestRequest) -> R + Send + Sync + 'static,
+    R: IntoRestResponse,
+{
+    fn handle(&self, request: ExtensionRestRequest) -> ExtensionRestResponse {
+        let usage = request.usage.clone();
+        with_usage(&usage, self(request).into_rest_response())
+    }
+}
+
+impl<F, R> Handler<()> for F
+where
+    F: Fn() -> R + Send + Sync + 'static,
+    R: IntoRestResponse,
+{
+    fn handle(&self, request: ExtensionRestRequest) -> ExtensionRestResponse {
+        if let Some(error) = request.unrecognized_params_error() {
+            return ExtensionRestResponse::error_from_request(request, &error);
+        }
+        with_usage(&request.usage, self().into_rest_response())
+    }
+}
+
+// Responses built with `text`, `json` and friends never see the request, so its usage
+// is copied onto them.
+fn with_usage(usage: &RequestUsage, mut response: ExtensionRestResponse) -> ExtensionRestResponse {
+    response.consumed_params.extend(usage.params());
+    response.content_consumed |= usage.content_consumed();
+    response
+}
+
+macro_rules! impl_handler {
+    ($($arg:ident),+) => {
+        impl<F, R, $($arg),+> Handler<($($arg,)+)> for F
+        where
+            F: Fn($($arg),+) -> R + Send + Sync + 'static,
+            R: IntoRestResponse,
+            $($arg: FromRequest,)+
+        {
+            #[allow(non_snake_case)]
+            fn handle(&self, mut request: ExtensionRestRequest) -> ExtensionRestResponse {
+                $(
+                    let $arg = match $arg::from_request(&mut request) {
+                        Ok(value) => value,
+                        Err(error) => {
+                            return ExtensionRestResponse::error_from_request(request, &error)
+                        }
+                    };
+                )+
+                // Extractors are the only way these handlers read params, so unknown
+                // ones are rejected before the handler runs.
+                if let Some(error) = request.unrecognized_params_error() {
+                    return ExtensionRestResponse::error_from_request(request, &error);
+                }
+                let response = self($($arg),+).into_rest_response();
+                with_usage(&request.usage, response)
+            }
+        }
+    };
+}
+
+impl_handler!(T1);
+impl_handler!(T1, T2);
+impl_handler!(T1, T2, T3);
+impl_handler!(T1, T2, T3, T4);
+impl_handler!(T1, T2, T3, T4, T5);
+impl_handler!(T1, T2, T3, T4, T5, T6);
+
+#[cfg(test)]
+mod tests {
+    use super::{Handler, Header, Json, Path, Principal, Query, XOpaqueId};
+    use crate::error::ExtensionError;
+    use crate::header::HeaderMap;
+    use crate::json::JsonValue;
+    use crate::rest::{ExtensionRestRequest, HttpVersion, RestMethod, RestStatus};
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn request(content: &[u8]) -> ExtensionRestRequest {
+        let mut request = ExtensionRestRequest::new(
+            RestMethod::Put,
+            "/indices/logs/docs/7?refresh=true".into(),
+            "/indices/logs/docs/7".into(),
+            BTreeMap::from([("refresh".to_string(), "true".to_string())]),
+            HeaderMap::from(BTreeMap::from([(
+                "x-opaque-id".to_string(),
+                vec!["trace-1".to_string()],
+            )])),
+            None,
+            content.to_vec(),
+            "principal-token".into(),
+            HttpVersion::Http11,
+        );
+        request.route_path = "/indices/{index}/docs/{id}".into();
+        request.path_params = BTreeMap::from([
+            ("id".to_string(), "7".to_string()),
+            ("index".to_string(), "logs".to_string()),
+        ]);
+        request
+    }
+
+    fn put_document(
+        Path((index, id)): Path<(String, u32)>,
+        Query(params): Query<BTreeMap<String, String>>,
+        Json(body): Json<JsonValue>,
+        opaque_id: Option<Header<XOpaqueId>>,
+        Principal(principal): Principal,
+    ) -> Result<String, ExtensionError> {
+        Ok(format!(
+            "{index}/{id} refresh={} body={body} opaque_id={} principal={principal}",
+            params["refresh"],
+            opaque_id.map(|header| header.value).unwrap_or_default()
+        ))
+    }
+
+    #[test]
+    fn extractors_feed_handler_arguments() {
+        let response = put_document.handle(request(br#"{"a":1}"#));
+        assert_eq!(response.status, RestStatus::Ok);
+        assert_eq!(
+            String::from_utf8(response.content).unwrap(),
+            r#"logs/7 refresh=true body={"a":1} opaque_id=trace-1 principal=principal-token"#
+        );
+        assert!(response.consumed_params.contains("refresh"));
+        assert!(response.content_consumed);
+    }
+
+    #[test]
+    fn extraction_failures_answer_bad_request() {
+        let response = put_document.handle(request(b"{"));
+        assert_eq!(response.status, RestStatus::BadRequest);
+        let body = JsonValue::parse_bytes(&response.content).unwrap();
+        assert_eq!(
+            body.get("error")
+                .and_then(|error| error.get("type"))
+                .and_then(JsonValue::as_str),
+            Some("parse_exception")
+        );
+        assert!(response.consumed_params.contains("refresh"));
+
+        let mut invalid_id = request(br#"{"a":1}"#);
+        invalid_id.path_params.insert("id".into(), "seven".into());
+        let response = put_document.handle(invalid_id);
+        assert_eq!(response.status, RestStatus::BadRequest);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn typed_queries_consume_only_their_fields() {
+        #[derive(serde::Deserialize)]
+        struct Refresh {
+            refresh: bool,
+            timeout: Option<u32>,
+        }
+
+        let handler = |Query(params): Query<Refresh>| {
+            format!("refresh={} timeout={:?}", params.refresh, params.timeout)
+        };
+        let mut known = request(b"");
+        known.params.insert("timeout".into(), "30".into());
+        let response = Handler::<(Query<Refresh>,)>::handle(&handler, known);
+        assert_eq!(response.content, b"refresh=true timeout=Some(30)");
+        assert!(response.consumed_params.contains("timeout"));
+
+        let mut unknown = request(b"");
+        unknown.params.insert("timout".into(), "30".into());
+        let response = Handler::<(Query<Refresh>,)>::handle(&handler, unknown);
+        assert_eq!(response.status, RestStatus::BadRequest);
+        assert!(String::from_utf8(response.content)
+            .unwrap()
+            .contains("contains unrecognized parameter: [timout]"));
+
+        let mut invalid = request(b"");
+        invalid.params.insert("timeout".into(), "soon".into());
+        let response = Handler::<(Query<Refresh>,)>::handle(&handler, invalid);
+        assert_eq!(response.status, RestStatus::BadRequest);
+    }
+
+    #[test]
+    fn optional_extractors_only_skip_absent_values() {
+        let handler = |body: Option<Json<JsonValue>>, opaque_id: Option<Header<XOpaqueId>>| {
+            format!(
+                "body={} opaque_id={}",
+                body.map(|Json(body)| body.to_string()).unwrap_or_default(),
+                opaque_id.map(|header| header.value).unwrap_or_default()
+            )
+        };
+        let mut absent = request(b"");
+        absent.params.clear();
+        absent.headers = HeaderMap::new();
+        let response = Handler::<(Option<Json<JsonValue>>, Option<Header<XOpaqueId>>)>::handle(
+            &handler, absent,
+        );
+        assert_eq!(response.content, b"body= opaque_id=");
+
+        let mut malformed = request(b"{");
+        malformed.params.clear();
+        let response = Handler::<(Option<Json<JsonValue>>, Option<Header<XOpaqueId>>)>::handle(
+            &handler, malformed,
+        );
+        assert_eq!(response.status, RestStatus::BadRequest);
+    }
+
+    #[test]
+    fn unknown_params_are_rejected_before_extracted_handlers_run() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let handler = move |Path((index, _)): Path<(String, u32)>| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            index
+        };
+        let response = Handler::<(Path<(String, u32)>,)>::handle(&handler, request(b""));
+        assert_eq!(response.status, RestStatus::BadRequest);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+}
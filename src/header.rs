@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+pub const ALLOW: &str = "Allow";
+pub const AUTHORIZATION: &str = "Authorization";
+pub const CONTENT_TYPE: &str = "Content-Type";
+pub const LOCATION: &str = "Location";
+pub const RETRY_AFTER: &str = "Retry-After";
+pub const WARNING: &str = "Warning";
+pub const X_OPAQUE_ID: &str = "X-Opaque-Id";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HeaderEntry {
+    name: String,
+    values: Vec<String>,
+}
+
+// HTTP header names compare case-insensitively. Entries are keyed by the lowercased
+// name but keep the spelling they were first inserted with for the wire.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: BTreeMap<String, HeaderEntry>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(&name.to_ascii_lowercase())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.get_all(name).first().map(String::as_str)
+    }
+
+    pub fn get_all(&self, name: &str) -> &[String] {
+        self.entries
+            .get(&name.to_ascii_lowercase())
+            .map(|entry| entry.values.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.insert_all(name, vec![value.into()]);
+    }
+
+    pub fn insert_all(&mut self, name: impl Into<String>, values: Vec<String>) {
+        self.entry(name.into()).values = values;
+    }
+
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.entry(name.into()).values.push(value.into());
+    }
+
+    fn entry(&mut self, name: String) -> &mut HeaderEntry {
+        self.entries
+            .entry(name.to_ascii_lowercase())
+            .or_insert_with(|| HeaderEntry {
+                name,
+                values: Vec::new(),
+            })
+    }
+
+    pub fn remove(&mut self, name: &str) -> Vec<String> {
+        self.entries
+            .remove(&name.to_ascii_lowercase())
+            .map(|entry| entry.values)
+            .unwrap_or_default()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.entries
+            .values()
+            .map(|entry| (entry.name.as_str(), entry.values.as_slice()))
+    }
+
+    pub fn location(&self) -> Option<&str> {
+        self.get(LOCATION)
+    }
+
+    pub fn set_location(&mut self, location: impl Into<String>) {
+        self.insert(LOCATION, location);
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        self.get_all(WARNING)
+    }
+
+    pub fn add_warning(&mut self, warning: impl Into<String>) {
+        self.append(WARNING, warning);
+    }
+
+    pub fn opaque_id(&self) -> Option<&str> {
+        self.get(X_OPAQUE_ID)
+    }
+
+    pub fn set_opaque_id(&mut self, opaque_id: impl Into<String>) {
+        self.insert(X_OPAQUE_ID, opaque_id);
+    }
+
+    // Only the delay-seconds form is understood; HTTP dates yield None.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.get(RETRY_AFTER)?
+            .trim()
+            .parse()
+            .ok()
+            .map(Duration::from_secs)
+    }
+
+    pub fn set_retry_after(&mut self, delay: Duration) {
+        let seconds = delay.as_secs() + u64::from(delay.subsec_nanos() > 0);
+        self.insert(RETRY_AFTER, seconds.to_string());
+    }
+}
+
+impl From<BTreeMap<String, Vec<String>>> for HeaderMap {
+    fn from(headers: BTreeMap<String, Vec<String>>) -> Self {
+        headers.into_iter().collect()
+    }
+}
+
+// Repeated names are merged rather than replaced, as they are on the wire.
+impl FromIterator<(String, Vec<String>)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (String, Vec<String>)>>(iter: I) -> Self {
+        let mut headers = Self::new();
+        for (name, values) in iter {
+            for value in values {
+                headers.append(name.clone(), value);
+            }
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderMap;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    #[test]
+    fn lookups_ignore_case_and_keep_every_value() {
+        let headers = HeaderMap::from(BTreeMap::from([
+            (
+                "content-type".to_string(),
+                vec!["application/json".to_string()],
+            ),
+            (
+                "Warning".to_string(),
+                vec!["299 first".to_string(), "299 second".to_string()],
+            ),
+        ]));
+        assert_eq!(headers.get("Content-Type"), Some("application/json"));
+        assert_eq!(headers.get("CONTENT-TYPE"), Some("application/json"));
+        assert_eq!(headers.warnings(), ["299 first", "299 second"]);
+        assert!(headers.get_all("Location").is_empty());
+
+        let mut headers = headers;
+        headers.append("WARNING", "299 third");
+        headers.insert("content-TYPE", "text/plain");
+        assert_eq!(headers.warnings().len(), 3);
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec![
+                ("content-type", &["text/plain".to_string()][..]),
+                (
+                    "Warning",
+                    &[
+                        "299 first".to_string(),
+                        "299 second".to_string(),
+                        "299 third".to_string()
+                    ][..]
+                ),
+            ]
+        );
+        assert_eq!(headers.remove("warning").len(), 3);
+        assert_eq!(headers.len(), 1);
+    }
+
+    #[test]
+    fn typed_helpers_read_and_write_common_headers() {
+        let mut headers = HeaderMap::new();
+        headers.set_location("/items/7");
+        headers.set_opaque_id("trace-1");
+        headers.set_retry_after(Duration::from_millis(1500));
+        headers.add_warning("299 OpenSearch-3.6.0-unknown \"deprecated\"");
+
+        assert_eq!(headers.location(), Some("/items/7"));
+        assert_eq!(headers.opaque_id(), Some("trace-1"));
+        assert_eq!(headers.get("retry-after"), Some("2"));
+        assert_eq!(headers.retry_after(), Some(Duration::from_secs(2)));
+        assert_eq!(headers.warnings().len(), 1);
+
+        headers.insert("Retry-After", "Wed, 21 Oct 2015 07:28:00 GMT");
+        assert_eq!(headers.retry_after(), None);
+    }
+}
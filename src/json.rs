@@ -0,0 +1,698 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(BTreeMap<String, JsonValue>),
+}
+
+impl JsonValue {
+    pub fn parse(input: &str) -> Result<Self, JsonError> {
+        Parser::new(input).parse_document()
+    }
+
+    pub fn parse_bytes(input: &[u8]) -> Result<Self, JsonError> {
+        let input = std::str::from_utf8(input).map_err(|error| {
+            JsonError::at(
+                "input is not valid UTF-8",
+                &input[..error.valid_up_to()],
+                error.valid_up_to(),
+            )
+        })?;
+        Self::parse(input)
+    }
+
+    pub fn object<K, I>(entries: I) -> Self
+    where
+        K: Into<String>,
+        I: IntoIterator<Item = (K, JsonValue)>,
+    {
+        Self::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?.get(key)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Self::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Self::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Integer(value) => Some(*value as f64),
+            Self::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&BTreeMap<String, JsonValue>> {
+        match self {
+            Self::Object(entries) => Some(entries),
+            _ => None,
+        }
+    }
+
+    pub fn to_pretty_string(&self) -> String {
+        let mut output = String::new();
+        write_pretty(self, 0, &mut output);
+        output
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut output = String::new();
+        write_compact(self, &mut output);
+        formatter.write_str(&output)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for JsonValue {
+    fn from(value: i32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<i64> for JsonValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<u32> for JsonValue {
+    fn from(value: u32) -> Self {
+        Self::Integer(value.into())
+    }
+}
+
+impl From<u64> for JsonValue {
+    fn from(value: u64) -> Self {
+        i64::try_from(value).map_or(Self::Float(value as f64), Self::Integer)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.into())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(values: Vec<T>) -> Self {
+        Self::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl From<BTreeMap<String, JsonValue>> for JsonValue {
+    fn from(entries: BTreeMap<String, JsonValue>) -> Self {
+        Self::Object(entries)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub message: String,
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl JsonError {
+    fn at(message: impl Into<String>, consumed: &[u8], offset: usize) -> Self {
+        let line = consumed.iter().filter(|byte| **byte == b'\n').count() + 1;
+        let line_start = consumed
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&consumed[line_start..])
+            .chars()
+            .count()
+            + 1;
+        Self {
+            message: message.into(),
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "{} at line {} column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            position: 0,
+            depth: 0,
+        }
+    }
+
+    fn parse_document(mut self) -> Result<JsonValue, JsonError> {
+        self.skip_whitespace();
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        if self.position < self.input.len() {
+            return Err(self.error("unexpected trailing characters"));
+        }
+        Ok(value)
+    }
+
+    fn error(&self, message: impl Into<String>) -> JsonError {
+        let offset = self.position.min(self.input.len());
+        JsonError::at(message, &self.input[..offset], offset)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.position += 1;
+        Some(byte)
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(byte) if byte == expected => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(byte) => Err(self.error(format!(
+                "expected '{}' but found '{}'",
+                expected as char, byte as char
+            ))),
+            None => Err(self.error(format!(
+                "expected '{}' but reached end of input",
+                expected as char
+            ))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.position += 1;
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, JsonError> {
+        match self.peek() {
+            Some(b'{') => self.nested(Self::parse_object),
+            Some(b'[') => self.nested(Self::parse_array),
+            Some(b'"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some(b't') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.parse_literal("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(byte) => Err(self.error(format!("unexpected character '{}'", byte as char))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<JsonValue, JsonError>,
+    ) -> Result<JsonValue, JsonError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(format!("nesting deeper than {MAX_DEPTH} levels")));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        if self.input[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(value)
+        } else {
+            Err(self.error(format!("invalid literal, expected '{literal}'")))
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, JsonError> {
+        self.expect(b'{')?;
+        let mut entries = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.position += 1;
+            return Ok(JsonValue::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected string object key"));
+            }
+            let key_start = self.position;
+            let key = self.parse_string()?;
+            if entries.contains_key(&key) {
+                self.position = key_start;
+                return Err(self.error(format!("duplicate object key [{key}]")));
+            }
+            self.skip_whitespace();
+            self.expect(b':')?;
+            self.skip_whitespace();
+            let value = self.parse_value()?;
+            entries.insert(key, value);
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b'}') => return Ok(JsonValue::Object(entries)),
+                _ => {
+                    self.position = self.position.saturating_sub(1);
+                    return Err(self.error("expected ',' or '}' after object entry"));
+                }
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, JsonError> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.position += 1;
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            self.skip_whitespace();
+            values.push(self.parse_value()?);
+            self.skip_whitespace();
+            match self.next() {
+                Some(b',') => continue,
+                Some(b']') => return Ok(JsonValue::Array(values)),
+                _ => {
+                    self.position = self.position.saturating_sub(1);
+                    return Err(self.error("expected ',' or ']' after array element"));
+                }
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut output = String::new();
+        loop {
+            let start = self.position;
+            while matches!(self.peek(), Some(byte) if byte != b'"' && byte != b'\\' && byte >= 0x20)
+            {
+                self.position += 1;
+            }
+            // The input came from a `&str` and we only stop on ASCII bytes, so this slice is valid UTF-8.
+            output.push_str(
+                std::str::from_utf8(&self.input[start..self.position]).unwrap_or_default(),
+            );
+
+            match self.next() {
+                Some(b'"') => return Ok(output),
+                Some(b'\\') => self.parse_escape(&mut output)?,
+                Some(_) => {
+                    self.position -= 1;
+                    return Err(self.error("unescaped control character in string"));
+                }
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self, output: &mut String) -> Result<(), JsonError> {
+        let escaped = match self.next() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => self.parse_unicode_escape()?,
+            _ => {
+                self.position = self.position.saturating_sub(1);
+                return Err(self.error("invalid escape sequence"));
+            }
+        };
+        output.push(escaped);
+        Ok(())
+    }
+
+    fn parse_unicode_escape(&mut self) -> Result<char, JsonError> {
+        let first = self.parse_hex4()?;
+        let code_point = if (0xD800..0xDC00).contains(&first) {
+            if !self.input[self.position..].starts_with(b"\\u") {
+                return Err(self.error("unpaired surrogate in unicode escape"));
+            }
+            self.position += 2;
+            let second = self.parse_hex4()?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err(self.error("invalid low surrogate in unicode escape"));
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+
+        char::from_u32(code_point).ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, JsonError> {
+        let Some(digits) = self.input.get(self.position..self.position + 4) else {
+            return Err(self.error("truncated unicode escape"));
+        };
+        // from_str_radix alone would also take a leading '+'.
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(self.error("invalid unicode escape"));
+        }
+        let value = digits.iter().fold(0, |value, digit| {
+            (value << 4) | (*digit as char).to_digit(16).unwrap_or(0)
+        });
+        self.position += 4;
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, JsonError> {
+        let start = self.position;
+        if self.peek() == Some(b'-') {
+            self.position += 1;
+        }
+
+        match self.peek() {
+            Some(b'0') => {
+                self.position += 1;
+                if matches!(self.peek(), Some(b'0'..=b'9')) {
+                    return Err(self.error("leading zeros are not allowed"));
+                }
+            }
+            Some(b'1'..=b'9') => self.skip_digits(),
+            _ => return Err(self.error("expected digit")),
+        }
+
+        let mut is_float = false;
+        if self.peek() == Some(b'.') {
+            is_float = true;
+            self.position += 1;
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("expected digit after decimal point"));
+            }
+            self.skip_digits();
+        }
+
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            is_float = true;
+            self.position += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if !matches!(self.peek(), Some(b'0'..=b'9')) {
+                return Err(self.error("expected digit in exponent"));
+            }
+            self.skip_digits();
+        }
+
+        let text = std::str::from_utf8(&self.input[start..self.position]).unwrap_or_default();
+        if !is_float {
+            if let Ok(value) = text.parse::<i64>() {
+                return Ok(JsonValue::Integer(value));
+            }
+        }
+        text.parse::<f64>()
+            .map(JsonValue::Float)
+            .map_err(|_| self.error("invalid number"))
+    }
+
+    fn skip_digits(&mut self) {
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.position += 1;
+        }
+    }
+}
+
+fn write_compact(value: &JsonValue, output: &mut String) {
+    match value {
+        JsonValue::Array(values) => {
+            output.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_compact(value, output);
+            }
+            output.push(']');
+        }
+        JsonValue::Object(entries) => {
+            output.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                write_string(key, output);
+                output.push(':');
+                write_compact(value, output);
+            }
+            output.push('}');
+        }
+        scalar => write_scalar(scalar, output),
+    }
+}
+
+// Mirrors the Jackson default pretty printer used by OpenSearch's `?pretty`.
+fn write_pretty(value: &JsonValue, indent: usize, output: &mut String) {
+    match value {
+        JsonValue::Array(values) if !values.is_empty() => {
+            output.push_str("[ ");
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    output.push_str(", ");
+                }
+                write_pretty(value, indent, output);
+            }
+            output.push_str(" ]");
+        }
+        JsonValue::Object(entries) if !entries.is_empty() => {
+            output.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    output.push(',');
+                }
+                output.push('\n');
+                push_indent(indent + 1, output);
+                write_string(key, output);
+                output.push_str(" : ");
+                write_pretty(value, indent + 1, output);
+            }
+            output.push('\n');
+            push_indent(indent, output);
+            output.push('}');
+        }
+        JsonValue::Array(_) => output.push_str("[ ]"),
+        JsonValue::Object(_) => output.push_str("{ }"),
+        scalar => write_scalar(scalar, output),
+    }
+}
+
+fn push_indent(indent: usize, output: &mut String) {
+    for _ in 0..indent {
+        output.push_str("  ");
+    }
+}
+
+fn write_scalar(value: &JsonValue, output: &mut String) {
+    match value {
+        JsonValue::Null => output.push_str("null"),
+        JsonValue::Bool(value) => output.push_str(if *value { "true" } else { "false" }),
+        JsonValue::Integer(value) => {
+            let _ = write!(output, "{value}");
+        }
+        JsonValue::Float(value) if !value.is_finite() => output.push_str("null"),
+        JsonValue::Float(value) if value.fract() == 0.0 && value.abs() < 1e16 => {
+            let _ = write!(output, "{value:.1}");
+        }
+        JsonValue::Float(value) => {
+            let _ = write!(output, "{value}");
+        }
+        JsonValue::String(value) => write_string(value, output),
+        JsonValue::Array(_) | JsonValue::Object(_) => write_compact(value, output),
+    }
+}
+
+fn write_string(value: &str, output: &mut String) {
+    output.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{8}' => output.push_str("\\b"),
+            '\u{c}' => output.push_str("\\f"),
+            character if (character as u32) < 0x20 => {
+                let _ = write!(output, "\\u{:04x}", character as u32);
+            }
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonValue;
+
+    #[test]
+    fn parses_and_serializes_nested_documents() {
+        let value = JsonValue::parse(
+            r#" {"name":"hello","count":3,"ratio":0.5,"tags":["a","b"],"nested":{"ok":true,"missing":null},"escaped":"line\nbreak é 😀"} "#,
+        )
+        .unwrap();
+
+        assert_eq!(value.get("name").and_then(JsonValue::as_str), Some("hello"));
+        assert_eq!(value.get("count").and_then(JsonValue::as_i64), Some(3));
+        assert_eq!(value.get("ratio").and_then(JsonValue::as_f64), Some(0.5));
+        assert_eq!(
+            value.get("escaped").and_then(JsonValue::as_str),
+            Some("line\nbreak é 😀")
+        );
+        assert_eq!(
+            value.to_string(),
+            r#"{"count":3,"escaped":"line\nbreak é 😀","name":"hello","nested":{"missing":null,"ok":true},"ratio":0.5,"tags":["a","b"]}"#
+        );
+        assert_eq!(JsonValue::parse(&value.to_string()).unwrap(), value);
+        assert_eq!(JsonValue::Float(2.0).to_string(), "2.0");
+    }
+
+    #[test]
+    fn pretty_output_matches_opensearch_layout() {
+        let value = JsonValue::object([
+            ("acknowledged", JsonValue::from(true)),
+            ("indices", JsonValue::from(vec!["a", "b"])),
+            ("shards", JsonValue::object([("total", JsonValue::from(2))])),
+        ]);
+        assert_eq!(
+            value.to_pretty_string(),
+            "{\n  \"acknowledged\" : true,\n  \"indices\" : [ \"a\", \"b\" ],\n  \"shards\" : {\n    \"total\" : 2\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn strict_parser_reports_error_positions() {
+        let error = JsonValue::parse("{\n  \"a\": 1,\n  \"b\": [1, 2,]\n}").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 14));
+        assert_eq!(
+            error.to_string(),
+            "unexpected character ']' at line 3 column 14"
+        );
+
+        assert!(JsonValue::parse("{\"a\":1,}").is_err());
+        assert!(JsonValue::parse("01").is_err());
+        assert!(JsonValue::parse("\"tab\there\"").is_err());
+        assert!(JsonValue::parse("[1] extra").is_err());
+        assert!(JsonValue::parse(&"[".repeat(200)).is_err());
+    }
+
+    #[test]
+    fn rejects_signed_unicode_escapes_and_duplicate_keys() {
+        assert_eq!(
+            JsonValue::parse(r#""\u00e9""#).unwrap(),
+            JsonValue::String("é".into())
+        );
+        assert!(JsonValue::parse(r#""\u+041""#).is_err());
+        assert!(JsonValue::parse(r#""\u-041""#).is_err());
+
+        let error = JsonValue::parse(r#"{"a": 1, "a": 2}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "duplicate object key [a] at line 1 column 10"
+        );
+        assert!(JsonValue::parse(r#"{"a": {"b": 1}, "c": {"b": 2}}"#).is_ok());
+    }
+}
@@ -1,5 +1,20 @@
+pub mod cbor;
+pub mod cli;
+pub mod context;
+pub mod error;
 pub mod extension;
+pub mod extract;
+pub mod header;
 pub mod host;
+pub mod identity;
+pub mod json;
+pub mod log;
+pub mod middleware;
+pub mod pool;
 pub mod rest;
+pub mod smile;
 pub mod stream;
+pub mod tls;
 pub mod transport;
+pub mod xcontent;
+pub mod yaml;
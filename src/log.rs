@@ -0,0 +1,648 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Instant;
+
+pub const LEVEL_ENV: &str = "OPENSEARCH_SDK_RS_LOG";
+pub const TRACE_ENV: &str = "OPENSEARCH_SDK_RS_TRACE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub const ALL: [Level; 5] = [
+        Self::Error,
+        Self::Warn,
+        Self::Info,
+        Self::Debug,
+        Self::Trace,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|level| level.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.as_str())
+    }
+}
+
+pub type Field<'a> = (&'static str, &'a dyn fmt::Display);
+
+// One log line. `span_fields` are the fields of the enclosing span, if any, so a
+// handler's own log lines carry the request ID of the REST dispatch around them.
+pub struct Record<'a> {
+    pub level: Level,
+    pub message: &'a str,
+    pub fields: &'a [Field<'a>],
+    pub span: Option<&'a str>,
+    pub span_fields: &'a [(&'static str, String)],
+}
+
+impl Record<'_> {
+    pub fn fields(&self) -> impl Iterator<Item = (&str, String)> {
+        self.span_fields
+            .iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .chain(
+                self.fields
+                    .iter()
+                    .map(|(name, value)| (*name, value.to_string())),
+            )
+    }
+}
+
+pub trait Logger: Send + Sync + 'static {
+    fn log(&self, record: &Record<'_>);
+}
+
+impl<F> Logger for F
+where
+    F: Fn(&Record<'_>) + Send + Sync + 'static,
+{
+    fn log(&self, record: &Record<'_>) {
+        self(record)
+    }
+}
+
+// The default logger: `level message span=name key=value ...` on stderr.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StderrLogger;
+
+impl Logger for StderrLogger {
+    fn log(&self, record: &Record<'_>) {
+        let mut line = format!("[{}] {}", record.level, record.message);
+        if let Some(span) = record.span {
+            line.push_str(&format!(" span={span}"));
+        }
+        for (name, value) in record.fields() {
+            line.push_str(&format!(" {name}={value}"));
+        }
+        let _ = writeln!(io::stderr().lock(), "{line}");
+    }
+}
+
+// Forwards records as `tracing` events with the `opensearch_sdk_rs` target, inside
+// the `tracing` span each `Span` opens at its own level. Span fields live on that
+// span, so events only carry their own fields.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingLogger;
+
+#[cfg(feature = "tracing")]
+impl Logger for TracingLogger {
+    fn log(&self, record: &Record<'_>) {
+        tracing_event(record);
+    }
+}
+
+// `tracing` needs field names at compile time. These are the ones the SDK itself
+// logs; any other field is joined into a single `fields` value.
+#[cfg(feature = "tracing")]
+macro_rules! with_sdk_fields {
+    ($callback:ident) => {
+        $callback!(
+            request_id,
+            action,
+            method,
+            path,
+            opaque_id,
+            route,
+            status,
+            unique_id,
+            register_request_id,
+            rest_actions_acknowledged,
+            environment_request_id,
+            environment_settings_bytes,
+            latency_ms,
+            correlation_id,
+            timeout_ms,
+            error
+        );
+    };
+}
+
+#[cfg(feature = "tracing")]
+macro_rules! tracing_callsites {
+    ($($field:ident),+) => {
+        fn tracing_span(level: Level, name: &'static str) -> tracing::Span {
+            use tracing::field::Empty;
+            match level {
+                Level::Error => tracing::error_span!(
+                    target: "opensearch_sdk_rs", "opensearch_sdk_rs",
+                    span = name, $($field = Empty,)+ fields = Empty
+                ),
+                Level::Warn => tracing::warn_span!(
+                    target: "opensearch_sdk_rs", "opensearch_sdk_rs",
+                    span = name, $($field = Empty,)+ fields = Empty
+                ),
+                Level::Info => tracing::info_span!(
+                    target: "opensearch_sdk_rs", "opensearch_sdk_rs",
+                    span = name, $($field = Empty,)+ fields = Empty
+                ),
+                Level::Debug => tracing::debug_span!(
+                    target: "opensearch_sdk_rs", "opensearch_sdk_rs",
+                    span = name, $($field = Empty,)+ fields = Empty
+                ),
+                Level::Trace => tracing::trace_span!(
+                    target: "opensearch_sdk_rs", "opensearch_sdk_rs",
+                    span = name, $($field = Empty,)+ fields = Empty
+                ),
+            }
+        }
+
+        fn tracing_event(record: &Record<'_>) {
+            $(let mut $field = None;)+
+            let mut other = Vec::new();
+            for (name, value) in record.fields {
+                match *name {
+                    $(stringify!($field) => $field = Some(value.to_string()),)+
+                    _ => other.push(format!("{name}={value}")),
+                }
+            }
+            let fields = (!other.is_empty()).then(|| other.join(" "));
+            let message = record.message;
+            match record.level {
+                Level::Error => tracing::error!(
+                    target: "opensearch_sdk_rs",
+                    $($field = $field.as_deref(),)+ fields = fields.as_deref(), "{message}"
+                ),
+                Level::Warn => tracing::warn!(
+                    target: "opensearch_sdk_rs",
+                    $($field = $field.as_deref(),)+ fields = fields.as_deref(), "{message}"
+                ),
+                Level::Info => tracing::info!(
+                    target: "opensearch_sdk_rs",
+                    $($field = $field.as_deref(),)+ fields = fields.as_deref(), "{message}"
+                ),
+                Level::Debug => tracing::debug!(
+                    target: "opensearch_sdk_rs",
+                    $($field = $field.as_deref(),)+ fields = fields.as_deref(), "{message}"
+                ),
+                Level::Trace => tracing::trace!(
+                    target: "opensearch_sdk_rs",
+                    $($field = $field.as_deref(),)+ fields = fields.as_deref(), "{message}"
+                ),
+            }
+        }
+    };
+}
+
+#[cfg(feature = "tracing")]
+with_sdk_fields!(tracing_callsites);
+
+#[cfg(feature = "tracing")]
+fn record_tracing_fields(span: &tracing::Span, fields: &[(&'static str, String)]) {
+    let mut other = Vec::new();
+    for (name, value) in fields {
+        if span.has_field(*name) {
+            span.record(*name, value.as_str());
+        } else {
+            other.push(format!("{name}={value}"));
+        }
+    }
+    if !other.is_empty() {
+        span.record("fields", other.join(" "));
+    }
+}
+
+static LOGGER: RwLock<Option<Arc<dyn Logger>>> = RwLock::new(None);
+
+const UNSET: u8 = u8::MAX;
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(UNSET);
+
+thread_local! {
+    static CURRENT_SPAN: RefCell<Option<SpanScope>> = const { RefCell::new(None) };
+}
+
+struct SpanScope {
+    name: &'static str,
+    fields: Vec<(&'static str, String)>,
+    #[cfg(feature = "tracing")]
+    inner: tracing::Span,
+}
+
+// Replaces the process-wide logger, `StderrLogger` until then.
+pub fn set_logger<L: Logger>(logger: L) {
+    if let Ok(mut current) = LOGGER.write() {
+        *current = Some(Arc::new(logger));
+    }
+}
+
+pub fn set_max_level(level: Level) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+// Defaults to `OPENSEARCH_SDK_RS_LOG`, or `trace` when `OPENSEARCH_SDK_RS_TRACE`
+// is set, or `info`.
+pub fn max_level() -> Level {
+    let stored = MAX_LEVEL.load(Ordering::Relaxed);
+    if let Some(level) = Level::ALL.get(usize::from(stored)) {
+        return *level;
+    }
+
+    let level = std::env::var(LEVEL_ENV)
+        .ok()
+        .and_then(|level| Level::parse(&level))
+        .or_else(|| std::env::var_os(TRACE_ENV).map(|_| Level::Trace))
+        .unwrap_or(Level::Info);
+    let _ = MAX_LEVEL.compare_exchange(UNSET, level as u8, Ordering::Relaxed, Ordering::Relaxed);
+    level
+}
+
+pub fn enabled(level: Level) -> bool {
+    level <= max_level()
+}
+
+pub fn log(level: Level, message: &str, fields: &[Field<'_>]) {
+    if !enabled(level) {
+        return;
+    }
+
+    let logger = LOGGER.read().ok().and_then(|logger| logger.clone());
+    CURRENT_SPAN.with(|span| {
+        let span = span.borrow();
+        let record = Record {
+            level,
+            message,
+            fields,
+            span: span.as_ref().map(|span| span.name),
+            span_fields: span.as_ref().map_or(&[], |span| span.fields.as_slice()),
+        };
+        match &logger {
+            Some(logger) => logger.log(&record),
+            None => StderrLogger.log(&record),
+        }
+    });
+}
+
+pub fn error(message: &str, fields: &[Field<'_>]) {
+    log(Level::Error, message, fields);
+}
+
+pub fn warn(message: &str, fields: &[Field<'_>]) {
+    log(Level::Warn, message, fields);
+}
+
+pub fn info(message: &str, fields: &[Field<'_>]) {
+    log(Level::Info, message, fields);
+}
+
+pub fn debug(message: &str, fields: &[Field<'_>]) {
+    log(Level::Debug, message, fields);
+}
+
+pub fn trace(message: &str, fields: &[Field<'_>]) {
+    log(Level::Trace, message, fields);
+}
+
+// Adds a field to the span the current thread is running in, if any.
+pub fn record(name: &'static str, value: impl fmt::Display) {
+    CURRENT_SPAN.with(|span| {
+        if let Some(span) = span.borrow_mut().as_mut() {
+            set_field(&mut span.fields, name, value.to_string());
+            #[cfg(feature = "tracing")]
+            record_tracing_fields(&span.inner, &span.fields);
+        }
+    });
+}
+
+fn set_field(fields: &mut Vec<(&'static str, String)>, name: &'static str, value: String) {
+    match fields.iter_mut().find(|(field, _)| *field == name) {
+        Some((_, current)) => *current = value,
+        None => fields.push((name, value)),
+    }
+}
+
+// A unit of work with fields attached to every record logged inside it. When the
+// span is dropped it logs `<name> finished` at its level, with the fields recorded
+// so far and `latency_ms`. Spans are not tied to a thread until `in_scope`, so one
+// can be carried across the frames of a multi-step exchange.
+#[must_use = "a span logs when it is dropped"]
+pub struct Span {
+    level: Level,
+    name: &'static str,
+    fields: Vec<(&'static str, String)>,
+    started: Instant,
+    #[cfg(feature = "tracing")]
+    inner: tracing::Span,
+}
+
+impl Span {
+    pub fn new(level: Level, name: &'static str, fields: &[Field<'_>]) -> Self {
+        let span = Self {
+            level,
+            name,
+            fields: fields
+                .iter()
+                .map(|(field, value)| (*field, value.to_string()))
+                .collect(),
+            started: Instant::now(),
+            #[cfg(feature = "tracing")]
+            inner: tracing_span(level, name),
+        };
+        #[cfg(feature = "tracing")]
+        record_tracing_fields(&span.inner, &span.fields);
+        span
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn record(&mut self, name: &'static str, value: impl fmt::Display) {
+        set_field(&mut self.fields, name, value.to_string());
+        #[cfg(feature = "tracing")]
+        record_tracing_fields(&self.inner, &self.fields);
+    }
+
+    // Runs `call` with this span as the current thread's span. Fields recorded with
+    // the free `record` function during `call` end up on this span.
+    pub fn in_scope<T>(&mut self, call: impl FnOnce() -> T) -> T {
+        let scope = SpanScope {
+            name: self.name,
+            fields: mem::take(&mut self.fields),
+            #[cfg(feature = "tracing")]
+            inner: self.inner.clone(),
+        };
+        let previous = CURRENT_SPAN.with(|span| span.replace(Some(scope)));
+        let restore = RestoreSpan {
+            span: self,
+            previous,
+        };
+        #[cfg(feature = "tracing")]
+        let value = restore.span.inner.clone().in_scope(call);
+        #[cfg(not(feature = "tracing"))]
+        let value = call();
+        drop(restore);
+        value
+    }
+}
+
+impl fmt::Debug for Span {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("Span")
+            .field("level", &self.level)
+            .field("name", &self.name)
+            .field("fields", &self.fields)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if !enabled(self.level) {
+            return;
+        }
+        let latency_ms = format!("{:.3}", self.started.elapsed().as_secs_f64() * 1000.0);
+        let fields = mem::take(&mut self.fields);
+        let scope = SpanScope {
+            name: self.name,
+            fields,
+            #[cfg(feature = "tracing")]
+            inner: self.inner.clone(),
+        };
+        #[cfg(feature = "tracing")]
+        let _entered = self.inner.enter();
+        let previous = CURRENT_SPAN.with(|span| span.replace(Some(scope)));
+        log(
+            self.level,
+            &format!("{} finished", self.name),
+            &[("latency_ms", &latency_ms)],
+        );
+        CURRENT_SPAN.with(|span| span.replace(previous));
+    }
+}
+
+// Puts the span's fields back, even if the scoped call panics.
+struct RestoreSpan<'a> {
+    span: &'a mut Span,
+    previous: Option<SpanScope>,
+}
+
+impl Drop for RestoreSpan<'_> {
+    fn drop(&mut self) {
+        let scope = CURRENT_SPAN.with(|span| span.replace(self.previous.take()));
+        if let Some(scope) = scope {
+            self.span.fields = scope.fields;
+        }
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone)]
+pub(crate) struct CapturedRecord {
+    pub level: Level,
+    pub message: String,
+    pub span: Option<String>,
+    pub fields: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+impl CapturedRecord {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Tests share one process-wide logger, so each installs this one and looks only
+// at the records it produced itself.
+#[cfg(test)]
+pub(crate) fn captured_records() -> Vec<CapturedRecord> {
+    use std::sync::{Mutex, OnceLock};
+
+    static RECORDS: OnceLock<Arc<Mutex<Vec<CapturedRecord>>>> = OnceLock::new();
+    let records = RECORDS.get_or_init(|| {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&records);
+        set_logger(move |record: &Record<'_>| {
+            sink.lock().unwrap().push(CapturedRecord {
+                level: record.level,
+                message: record.message.to_string(),
+                span: record.span.map(str::to_string),
+                fields: record
+                    .fields()
+                    .map(|(name, value)| (name.to_string(), value))
+                    .collect(),
+            });
+        });
+        set_max_level(Level::Trace);
+        records
+    });
+    records.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{captured_records, info, record, Level, Span};
+
+    #[test]
+    fn spans_attach_their_fields_and_log_latency_when_dropped() {
+        captured_records();
+        assert_eq!(Level::parse(" WARN "), Some(Level::Warn));
+        assert!(Level::Error < Level::Trace);
+
+        let mut span = Span::new(Level::Info, "log_test", &[("request_id", &42)]);
+        span.in_scope(|| {
+            info("handling", &[("step", &1)]);
+            record("status", 200);
+        });
+        assert_eq!(span.field("status"), Some("200"));
+        drop(span);
+        info("outside", &[("step", &2)]);
+
+        let records = captured_records()
+            .into_iter()
+            .filter(|record| {
+                record.span.as_deref() == Some("log_test") || record.message == "outside"
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].message, "handling");
+        assert_eq!(
+            records[0].fields,
+            [
+                ("request_id".to_string(), "42".to_string()),
+                ("step".to_string(), "1".to_string())
+            ]
+        );
+        assert_eq!(records[1].message, "log_test finished");
+        assert_eq!(records[1].level, Level::Info);
+        assert_eq!(records[1].field("status"), Some("200"));
+        assert!(records[1].field("latency_ms").is_some());
+        assert_eq!(records[2].span, None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tracing_spans_carry_fields_at_their_level() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        #[derive(Default)]
+        struct Fields(Vec<(String, String)>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .push((field.name().to_string(), format!("{value:?}")));
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.push((field.name().to_string(), value.to_string()));
+            }
+        }
+
+        #[derive(Default)]
+        struct Capture {
+            spans: Mutex<Vec<(tracing::Level, Fields)>>,
+            events: Mutex<Vec<Fields>>,
+        }
+
+        struct CaptureSubscriber(Arc<Capture>);
+
+        impl Subscriber for CaptureSubscriber {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+                let mut fields = Fields::default();
+                attributes.record(&mut fields);
+                let mut spans = self.0.spans.lock().unwrap();
+                spans.push((*attributes.metadata().level(), fields));
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.0.spans.lock().unwrap();
+                values.record(&mut spans[span.into_u64() as usize - 1].1);
+            }
+
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields::default();
+                event.record(&mut fields);
+                self.0.events.lock().unwrap().push(fields);
+            }
+
+            fn enter(&self, _span: &Id) {}
+
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let capture = Arc::new(Capture::default());
+        let subscriber = CaptureSubscriber(Arc::clone(&capture));
+        tracing::subscriber::with_default(subscriber, || {
+            let mut span = Span::new(Level::Debug, "tracing_test", &[("request_id", &7)]);
+            span.in_scope(|| {
+                record("status", 200);
+                record("custom", "x");
+            });
+            super::tracing_event(&super::Record {
+                level: Level::Warn,
+                message: "slow",
+                fields: &[("route", &"test:route"), ("step", &2)],
+                span: None,
+                span_fields: &[],
+            });
+        });
+
+        let spans = capture.spans.lock().unwrap();
+        let (level, fields) = &spans[0];
+        assert_eq!(*level, tracing::Level::DEBUG);
+        let field = |name: &str| {
+            fields
+                .0
+                .iter()
+                .rev()
+                .find(|(field, _)| field == name)
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(field("request_id").as_deref(), Some("7"));
+        assert_eq!(field("status").as_deref(), Some("200"));
+        assert_eq!(field("fields").as_deref(), Some("custom=x"));
+
+        let events = capture.events.lock().unwrap();
+        let event = &events.last().unwrap().0;
+        assert!(event.contains(&("route".to_string(), "test:route".to_string())));
+        assert!(event.contains(&("fields".to_string(), "step=2".to_string())));
+    }
+}
@@ -0,0 +1,109 @@
+use crate::rest::{ExtensionRestRequest, ExtensionRestResponse};
+use std::sync::Arc;
+
+pub trait Middleware: Send + Sync + 'static {
+    fn handle(&self, request: ExtensionRestRequest, next: Next<'_>) -> ExtensionRestResponse;
+}
+
+impl<F> Middleware for F
+where
+    F: for<'a> Fn(ExtensionRestRequest, Next<'a>) -> ExtensionRestResponse + Send + Sync + 'static,
+{
+    fn handle(&self, request: ExtensionRestRequest, next: Next<'_>) -> ExtensionRestResponse {
+        self(request, next)
+    }
+}
+
+pub type MiddlewareStack = Vec<Arc<dyn Middleware>>;
+
+// Layers run in registration order: the first registered layer sees the request first.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    layers: &'a [Arc<dyn Middleware>],
+    endpoint: &'a dyn Fn(ExtensionRestRequest) -> ExtensionRestResponse,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        layers: &'a [Arc<dyn Middleware>],
+        endpoint: &'a dyn Fn(ExtensionRestRequest) -> ExtensionRestResponse,
+    ) -> Self {
+        Self { layers, endpoint }
+    }
+
+    pub fn run(self, request: ExtensionRestRequest) -> ExtensionRestResponse {
+        match self.layers.split_first() {
+            Some((layer, layers)) => layer.handle(
+                request,
+                Next {
+                    layers,
+                    endpoint: self.endpoint,
+                },
+            ),
+            None => (self.endpoint)(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Middleware, Next};
+    use crate::header::HeaderMap;
+    use crate::rest::{
+        ExtensionRestRequest, ExtensionRestResponse, HttpVersion, RestMethod, RestStatus,
+    };
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    fn recording_layer(
+        label: &'static str,
+        events: &Arc<Mutex<Vec<String>>>,
+    ) -> Arc<dyn Middleware> {
+        let events = Arc::clone(events);
+        Arc::new(move |request: ExtensionRestRequest, next: Next<'_>| {
+            events.lock().unwrap().push(format!("{label}:before"));
+            let response = next.run(request);
+            events.lock().unwrap().push(format!("{label}:after"));
+            response
+        })
+    }
+
+    #[test]
+    fn layers_wrap_the_endpoint_in_registration_order() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let layers = vec![
+            recording_layer("outer", &events),
+            recording_layer("inner", &events),
+        ];
+        let endpoint_events = Arc::clone(&events);
+        let endpoint = move |_request: ExtensionRestRequest| {
+            endpoint_events.lock().unwrap().push("endpoint".into());
+            ExtensionRestResponse::text(RestStatus::Ok, "done")
+        };
+
+        let request = ExtensionRestRequest::new(
+            RestMethod::Get,
+            "/".into(),
+            "/".into(),
+            BTreeMap::new(),
+            HeaderMap::new(),
+            None,
+            Vec::new(),
+            String::new(),
+            HttpVersion::Http11,
+        );
+        let response = Next::new(&layers, &endpoint).run(request);
+
+        assert_eq!(response.content, b"done");
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                "outer:before",
+                "inner:before",
+                "endpoint",
+                "inner:after",
+                "outer:after"
+            ]
+        );
+    }
+}
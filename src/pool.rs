@@ -0,0 +1,159 @@
+use std::fmt;
+use std::io;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerPoolConfig {
+    pub threads: usize,
+    pub queue_capacity: usize,
+}
+
+impl WorkerPoolConfig {
+    pub const DEFAULT_QUEUE_CAPACITY: usize = 64;
+
+    pub fn new(threads: usize, queue_capacity: usize) -> Self {
+        Self {
+            threads: threads.max(1),
+            // A zero-capacity channel would only accept a job while a worker is
+            // already waiting, rejecting most requests under load.
+            queue_capacity: queue_capacity.max(1),
+        }
+    }
+}
+
+impl Default for WorkerPoolConfig {
+    fn default() -> Self {
+        let threads = thread::available_parallelism()
+            .map(|threads| threads.get())
+            .unwrap_or(4);
+        Self::new(threads, Self::DEFAULT_QUEUE_CAPACITY)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolSaturated;
+
+impl fmt::Display for PoolSaturated {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("worker pool queue is full")
+    }
+}
+
+impl std::error::Error for PoolSaturated {}
+
+pub struct WorkerPool {
+    sender: Option<SyncSender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(config: WorkerPoolConfig) -> io::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(config.queue_capacity.max(1));
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let mut workers = Vec::with_capacity(config.threads);
+        for index in 0..config.threads.max(1) {
+            let receiver = Arc::clone(&receiver);
+            let worker = thread::Builder::new()
+                .name(format!("opensearch-sdk-rs-worker-{index}"))
+                .spawn(move || run_worker(&receiver))?;
+            workers.push(worker);
+        }
+
+        Ok(Self {
+            sender: Some(sender),
+            workers,
+        })
+    }
+
+    pub fn try_execute<F>(&self, job: F) -> Result<(), PoolSaturated>
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let Some(sender) = &self.sender else {
+            return Err(PoolSaturated);
+        };
+
+        match sender.try_send(Box::new(job)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) | Err(TrySendError::Disconnected(_)) => Err(PoolSaturated),
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.sender.take();
+        let current = thread::current().id();
+        for worker in self.workers.drain(..) {
+            if worker.thread().id() != current {
+                let _ = worker.join();
+            }
+        }
+    }
+}
+
+fn run_worker(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        let job = {
+            let Ok(receiver) = receiver.lock() else {
+                return;
+            };
+            receiver.recv()
+        };
+
+        match job {
+            Ok(job) => job(),
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{WorkerPool, WorkerPoolConfig};
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn pool_runs_queued_jobs() {
+        assert_eq!(WorkerPoolConfig::new(0, 0), WorkerPoolConfig::new(1, 1));
+        let pool = WorkerPool::new(WorkerPoolConfig::new(2, 4)).unwrap();
+        let (sender, receiver) = mpsc::channel();
+
+        for value in 0..4 {
+            let sender = sender.clone();
+            pool.try_execute(move || sender.send(value).unwrap())
+                .unwrap();
+        }
+
+        let mut values = (0..4)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(5)).unwrap())
+            .collect::<Vec<_>>();
+        values.sort();
+        assert_eq!(values, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn saturated_pool_rejects_without_blocking() {
+        let pool = WorkerPool::new(WorkerPoolConfig::new(1, 1)).unwrap();
+        let (started_sender, started) = mpsc::channel();
+        let (release_sender, release) = mpsc::channel::<()>();
+
+        pool.try_execute(move || {
+            started_sender.send(()).unwrap();
+            let _ = release.recv();
+        })
+        .unwrap();
+        started.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        pool.try_execute(|| {}).unwrap();
+        assert!(pool.try_execute(|| {}).is_err());
+
+        release_sender.send(()).unwrap();
+    }
+}
@@ -1,5 +1,14 @@
+use crate::error::ExtensionError;
+use crate::header::{self, HeaderMap};
+use crate::identity::Identity;
+use crate::json::JsonValue;
+use crate::xcontent::{self, MediaType, XContentType};
 use std::collections::{BTreeMap, BTreeSet};
-use std::io;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RestMethod {
@@ -67,23 +76,207 @@ impl HttpVersion {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RestStatus {
+    Continue,
+    SwitchingProtocols,
     Ok,
+    Created,
     Accepted,
+    NonAuthoritativeInformation,
+    NoContent,
+    ResetContent,
+    PartialContent,
+    MultiStatus,
+    MultipleChoices,
+    MovedPermanently,
+    Found,
+    SeeOther,
+    NotModified,
+    UseProxy,
+    TemporaryRedirect,
+    BadRequest,
+    Unauthorized,
+    PaymentRequired,
+    Forbidden,
     NotFound,
+    MethodNotAllowed,
+    NotAcceptable,
+    ProxyAuthentication,
+    RequestTimeout,
+    Conflict,
+    Gone,
+    LengthRequired,
+    PreconditionFailed,
+    RequestEntityTooLarge,
+    RequestUriTooLong,
+    UnsupportedMediaType,
+    RequestedRangeNotSatisfied,
+    ExpectationFailed,
+    MisdirectedRequest,
+    UnprocessableEntity,
+    Locked,
+    FailedDependency,
+    TooManyRequests,
     InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    HttpVersionNotSupported,
+    InsufficientStorage,
 }
 
 impl RestStatus {
+    pub const ALL: [Self; 47] = [
+        Self::Continue,
+        Self::SwitchingProtocols,
+        Self::Ok,
+        Self::Created,
+        Self::Accepted,
+        Self::NonAuthoritativeInformation,
+        Self::NoContent,
+        Self::ResetContent,
+        Self::PartialContent,
+        Self::MultiStatus,
+        Self::MultipleChoices,
+        Self::MovedPermanently,
+        Self::Found,
+        Self::SeeOther,
+        Self::NotModified,
+        Self::UseProxy,
+        Self::TemporaryRedirect,
+        Self::BadRequest,
+        Self::Unauthorized,
+        Self::PaymentRequired,
+        Self::Forbidden,
+        Self::NotFound,
+        Self::MethodNotAllowed,
+        Self::NotAcceptable,
+        Self::ProxyAuthentication,
+        Self::RequestTimeout,
+        Self::Conflict,
+        Self::Gone,
+        Self::LengthRequired,
+        Self::PreconditionFailed,
+        Self::RequestEntityTooLarge,
+        Self::RequestUriTooLong,
+        Self::UnsupportedMediaType,
+        Self::RequestedRangeNotSatisfied,
+        Self::ExpectationFailed,
+        Self::MisdirectedRequest,
+        Self::UnprocessableEntity,
+        Self::Locked,
+        Self::FailedDependency,
+        Self::TooManyRequests,
+        Self::InternalServerError,
+        Self::NotImplemented,
+        Self::BadGateway,
+        Self::ServiceUnavailable,
+        Self::GatewayTimeout,
+        Self::HttpVersionNotSupported,
+        Self::InsufficientStorage,
+    ];
+
     pub fn to_wire(self) -> u32 {
+        self as u32
+    }
+
+    pub fn from_wire(value: u32) -> io::Result<Self> {
+        Self::ALL.get(value as usize).copied().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown REST status ordinal {value}"),
+            )
+        })
+    }
+
+    pub fn code(self) -> u16 {
         match self {
-            Self::Ok => 2,
-            Self::Accepted => 4,
-            Self::NotFound => 21,
-            Self::InternalServerError => 40,
+            Self::Continue => 100,
+            Self::SwitchingProtocols => 101,
+            Self::Ok => 200,
+            Self::Created => 201,
+            Self::Accepted => 202,
+            Self::NonAuthoritativeInformation => 203,
+            Self::NoContent => 204,
+            Self::ResetContent => 205,
+            Self::PartialContent => 206,
+            Self::MultiStatus => 207,
+            Self::MultipleChoices => 300,
+            Self::MovedPermanently => 301,
+            Self::Found => 302,
+            Self::SeeOther => 303,
+            Self::NotModified => 304,
+            Self::UseProxy => 305,
+            Self::TemporaryRedirect => 307,
+            Self::BadRequest => 400,
+            Self::Unauthorized => 401,
+            Self::PaymentRequired => 402,
+            Self::Forbidden => 403,
+            Self::NotFound => 404,
+            Self::MethodNotAllowed => 405,
+            Self::NotAcceptable => 406,
+            Self::ProxyAuthentication => 407,
+            Self::RequestTimeout => 408,
+            Self::Conflict => 409,
+            Self::Gone => 410,
+            Self::LengthRequired => 411,
+            Self::PreconditionFailed => 412,
+            Self::RequestEntityTooLarge => 413,
+            Self::RequestUriTooLong => 414,
+            Self::UnsupportedMediaType => 415,
+            Self::RequestedRangeNotSatisfied => 416,
+            Self::ExpectationFailed => 417,
+            Self::MisdirectedRequest => 421,
+            Self::UnprocessableEntity => 422,
+            Self::Locked => 423,
+            Self::FailedDependency => 424,
+            Self::TooManyRequests => 429,
+            Self::InternalServerError => 500,
+            Self::NotImplemented => 501,
+            Self::BadGateway => 502,
+            Self::ServiceUnavailable => 503,
+            Self::GatewayTimeout => 504,
+            Self::HttpVersionNotSupported => 505,
+            Self::InsufficientStorage => 506,
         }
     }
+
+    pub fn from_code(code: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|status| status.code() == code)
+    }
+
+    pub fn is_success(self) -> bool {
+        (200..300).contains(&self.code())
+    }
+
+    pub fn is_client_error(self) -> bool {
+        (400..500).contains(&self.code())
+    }
+
+    pub fn is_server_error(self) -> bool {
+        self.code() >= 500
+    }
+}
+
+impl TryFrom<u16> for RestStatus {
+    type Error = io::Error;
+
+    fn try_from(code: u16) -> io::Result<Self> {
+        Self::from_code(code).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("unknown HTTP status code {code}"),
+            )
+        })
+    }
+}
+
+impl From<RestStatus> for u16 {
+    fn from(status: RestStatus) -> Self {
+        status.code()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -92,15 +285,68 @@ pub struct ExtensionRestRequest {
     pub uri: String,
     pub path: String,
     pub params: BTreeMap<String, String>,
-    pub headers: BTreeMap<String, Vec<String>>,
+    pub path_params: BTreeMap<String, String>,
+    pub route_path: String,
+    pub headers: HeaderMap,
     pub media_type: Option<String>,
     pub content: Vec<u8>,
     pub principal_identifier_token: String,
+    // Set once the host's `JwtVerifier` has accepted the token.
+    pub principal_verified: bool,
     pub http_version: HttpVersion,
-    pub consumed_params: BTreeSet<String>,
-    pub content_consumed: bool,
+    pub usage: RequestUsage,
+}
+
+// Which params and whether the body were read. Clones share one record, so the host
+// still sees what a handler consumed after the request has moved into it.
+#[derive(Debug, Clone, Default)]
+pub struct RequestUsage {
+    state: Arc<Mutex<UsageState>>,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct UsageState {
+    params: BTreeSet<String>,
+    content: bool,
+}
+
+impl RequestUsage {
+    pub fn consume_param(&self, key: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            state.params.insert(key.to_string());
+        }
+    }
+
+    pub fn consume_content(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.content = true;
+        }
+    }
+
+    pub fn params(&self) -> BTreeSet<String> {
+        self.snapshot().params
+    }
+
+    pub fn content_consumed(&self) -> bool {
+        self.snapshot().content
+    }
+
+    fn snapshot(&self) -> UsageState {
+        self.state
+            .lock()
+            .map(|state| state.clone())
+            .unwrap_or_default()
+    }
+}
+
+impl PartialEq for RequestUsage {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.state, &other.state) || self.snapshot() == other.snapshot()
+    }
+}
+
+impl Eq for RequestUsage {}
+
 impl ExtensionRestRequest {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -108,7 +354,7 @@ impl ExtensionRestRequest {
         uri: String,
         path: String,
         params: BTreeMap<String, String>,
-        headers: BTreeMap<String, Vec<String>>,
+        headers: HeaderMap,
         media_type: Option<String>,
         content: Vec<u8>,
         principal_identifier_token: String,
@@ -119,19 +365,180 @@ impl ExtensionRestRequest {
             uri,
             path,
             params,
+            path_params: BTreeMap::new(),
+            route_path: String::new(),
             headers,
             media_type,
             content,
             principal_identifier_token,
+            principal_verified: false,
             http_version,
-            consumed_params: BTreeSet::new(),
-            content_consumed: false,
+            usage: RequestUsage::default(),
         }
     }
 
     pub fn route_key(&self) -> String {
         format!("{} {}", self.method.as_str(), self.path)
     }
+
+    pub fn param(&mut self, key: &str) -> Option<&str> {
+        self.usage.consume_param(key);
+        self.params.get(key).map(String::as_str)
+    }
+
+    pub fn param_or<'a>(&'a mut self, key: &str, default: &'a str) -> &'a str {
+        self.param(key).unwrap_or(default)
+    }
+
+    pub fn param_as<T>(&mut self, key: &str) -> Result<Option<T>, ExtensionError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let Some(value) = self.param(key) else {
+            return Ok(None);
+        };
+
+        value.parse().map(Some).map_err(|error| {
+            ExtensionError::bad_request(format!(
+                "failed to parse parameter [{key}] with value [{value}]: {error}"
+            ))
+        })
+    }
+
+    pub fn has_param(&self, key: &str) -> bool {
+        self.params.contains_key(key)
+    }
+
+    pub fn consumed_params(&self) -> BTreeSet<String> {
+        self.usage.params()
+    }
+
+    pub fn content_consumed(&self) -> bool {
+        self.usage.content_consumed()
+    }
+
+    pub fn unconsumed_params(&self) -> BTreeSet<String> {
+        let consumed = self.consumed_params();
+        self.params
+            .keys()
+            .filter(|key| !consumed.contains(*key))
+            .cloned()
+            .collect()
+    }
+
+    // The error OpenSearch answers with when a handler leaves params unread;
+    // response params such as `pretty` are always accepted.
+    pub fn unrecognized_params_error(&self) -> Option<ExtensionError> {
+        unrecognized_params_error(&self.path, self.params.keys(), &self.consumed_params())
+    }
+
+    pub fn content(&mut self) -> &[u8] {
+        self.usage.consume_content();
+        &self.content
+    }
+
+    pub fn has_content(&self) -> bool {
+        !self.content.is_empty()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    pub fn opaque_id(&self) -> Option<&str> {
+        self.headers.opaque_id()
+    }
+
+    pub fn identity(&self) -> Identity {
+        let identity = Identity::from_token(self.principal_identifier_token.clone());
+        if self.principal_verified {
+            identity.verified()
+        } else {
+            identity
+        }
+    }
+
+    pub fn content_media_type(&self) -> Result<Option<MediaType>, ExtensionError> {
+        let Some(media_type) = self
+            .media_type
+            .as_deref()
+            .or_else(|| self.header(header::CONTENT_TYPE))
+        else {
+            return Ok(None);
+        };
+        MediaType::parse(media_type)
+            .map(Some)
+            .map_err(|error| ExtensionError::not_acceptable(error.to_string()))
+    }
+
+    pub fn content_xcontent_type(&self) -> Result<XContentType, ExtensionError> {
+        let Some(media_type) = self.content_media_type()? else {
+            return Ok(XContentType::Json);
+        };
+        media_type.xcontent_type().ok_or_else(|| {
+            ExtensionError::not_acceptable(format!(
+                "Content-Type header [{media_type}] is not supported"
+            ))
+        })
+    }
+
+    pub fn response_xcontent_type(&mut self) -> Result<XContentType, ExtensionError> {
+        let format = self.param("format").map(str::to_string);
+        let content_type = self
+            .content_media_type()?
+            .and_then(|media_type| media_type.xcontent_type());
+        xcontent::negotiate(format.as_deref(), self.header("Accept"), content_type)
+            .map_err(|error| ExtensionError::not_acceptable(error.to_string()))
+    }
+
+    pub fn json_body(&mut self) -> Result<JsonValue, ExtensionError> {
+        if !self.has_content() {
+            return Err(ExtensionError::parse("request body is required"));
+        }
+
+        let xcontent_type = self.content_xcontent_type()?;
+        xcontent_type.decode(self.content()).map_err(|error| {
+            ExtensionError::parse(format!("failed to parse request body: {error}"))
+        })
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn parse_body<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, ExtensionError> {
+        if !self.has_content() {
+            return Err(ExtensionError::parse("request body is required"));
+        }
+
+        let parsed = match self.content_xcontent_type()? {
+            XContentType::Json => serde_json::from_slice(self.content()),
+            _ => serde_json::from_str(&self.json_body()?.to_string()),
+        };
+        parsed.map_err(|error| {
+            ExtensionError::parse(format!("failed to parse request body: {error}"))
+        })
+    }
+
+    pub fn path_param(&self, name: &str) -> Option<&str> {
+        self.path_params.get(name).map(String::as_str)
+    }
+
+    pub fn path_param_as<T>(&self, name: &str) -> Result<T, ExtensionError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let Some(value) = self.path_param(name) else {
+            return Err(ExtensionError::bad_request(format!(
+                "missing path parameter [{name}]"
+            )));
+        };
+
+        value.parse().map_err(|error| {
+            ExtensionError::bad_request(format!(
+                "failed to parse path parameter [{name}] with value [{value}]: {error}"
+            ))
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -139,25 +546,149 @@ pub struct ExtensionRestResponse {
     pub status: RestStatus,
     pub content_type: String,
     pub content: Vec<u8>,
-    pub headers: BTreeMap<String, Vec<String>>,
+    pub headers: HeaderMap,
     pub consumed_params: BTreeSet<String>,
     pub content_consumed: bool,
+    // When set, replaces `content` and is written into the response frame by the host.
+    pub stream: Option<StreamingBody>,
 }
 
 impl ExtensionRestResponse {
     pub const TEXT_CONTENT_TYPE: &'static str = "text/plain; charset=UTF-8";
+    pub const JSON_CONTENT_TYPE: &'static str = "application/json; charset=UTF-8";
+    pub const BINARY_CONTENT_TYPE: &'static str = "application/octet-stream";
+    pub const NDJSON_CONTENT_TYPE: &'static str = "application/x-ndjson";
 
     pub fn text(status: RestStatus, content: impl Into<String>) -> Self {
         Self {
             status,
             content_type: Self::TEXT_CONTENT_TYPE.into(),
             content: content.into().into_bytes(),
-            headers: BTreeMap::new(),
+            headers: HeaderMap::new(),
+            consumed_params: BTreeSet::new(),
+            content_consumed: false,
+            stream: None,
+        }
+    }
+
+    pub fn bytes(status: RestStatus, content: Vec<u8>) -> Self {
+        Self {
+            status,
+            content_type: Self::BINARY_CONTENT_TYPE.into(),
+            content,
+            headers: HeaderMap::new(),
             consumed_params: BTreeSet::new(),
             content_consumed: false,
+            stream: None,
+        }
+    }
+
+    pub fn json(status: RestStatus, value: impl Into<JsonValue>) -> Self {
+        Self {
+            status,
+            content_type: Self::JSON_CONTENT_TYPE.into(),
+            content: value.into().to_bytes(),
+            headers: HeaderMap::new(),
+            consumed_params: BTreeSet::new(),
+            content_consumed: false,
+            stream: None,
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn json_of<T: serde::Serialize + ?Sized>(status: RestStatus, value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(content) => Self {
+                status,
+                content_type: Self::JSON_CONTENT_TYPE.into(),
+                content,
+                headers: HeaderMap::new(),
+                consumed_params: BTreeSet::new(),
+                content_consumed: false,
+                stream: None,
+            },
+            Err(error) => Self::error(&ExtensionError::internal(format!(
+                "failed to serialize response body: {error}"
+            ))),
+        }
+    }
+
+    pub fn streaming(
+        status: RestStatus,
+        content_type: impl Into<String>,
+        body: StreamingBody,
+    ) -> Self {
+        Self {
+            status,
+            content_type: content_type.into(),
+            content: Vec::new(),
+            headers: HeaderMap::new(),
+            consumed_params: BTreeSet::new(),
+            content_consumed: false,
+            stream: Some(body),
+        }
+    }
+
+    pub fn ndjson<I>(status: RestStatus, values: I) -> Self
+    where
+        I: IntoIterator<Item = JsonValue> + Send + 'static,
+    {
+        Self::streaming(
+            status,
+            Self::NDJSON_CONTENT_TYPE,
+            StreamingBody::ndjson(values),
+        )
+    }
+
+    pub fn xcontent(status: RestStatus, xcontent_type: XContentType, value: &JsonValue) -> Self {
+        Self {
+            status,
+            content_type: xcontent_type.media_type().into(),
+            content: xcontent_type.encode(value),
+            headers: HeaderMap::new(),
+            consumed_params: BTreeSet::new(),
+            content_consumed: false,
+            stream: None,
+        }
+    }
+
+    pub fn negotiated(
+        mut request: ExtensionRestRequest,
+        status: RestStatus,
+        value: &JsonValue,
+    ) -> Self {
+        match request.response_xcontent_type() {
+            Ok(xcontent_type) => Self::from_request(
+                request,
+                status,
+                xcontent_type.media_type(),
+                xcontent_type.encode(value),
+            ),
+            Err(error) => Self::error_from_request(request, &error),
         }
     }
 
+    pub fn error(error: &ExtensionError) -> Self {
+        Self::json(error.status, error.to_json())
+    }
+
+    pub fn error_from_request(request: ExtensionRestRequest, error: &ExtensionError) -> Self {
+        Self::json_from_request(request, error.status, error.to_json())
+    }
+
+    pub fn json_from_request(
+        request: ExtensionRestRequest,
+        status: RestStatus,
+        value: impl Into<JsonValue>,
+    ) -> Self {
+        Self::from_request(
+            request,
+            status,
+            Self::JSON_CONTENT_TYPE,
+            value.into().to_bytes(),
+        )
+    }
+
     pub fn from_request(
         request: ExtensionRestRequest,
         status: RestStatus,
@@ -168,9 +699,520 @@ impl ExtensionRestResponse {
             status,
             content_type: content_type.into(),
             content,
-            headers: BTreeMap::new(),
-            consumed_params: request.consumed_params,
-            content_consumed: request.content_consumed,
+            headers: HeaderMap::new(),
+            consumed_params: request.consumed_params(),
+            content_consumed: request.content_consumed(),
+            stream: None,
         }
     }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.headers.set_location(location);
+        self
+    }
+
+    pub fn with_warning(mut self, warning: impl Into<String>) -> Self {
+        self.headers.add_warning(warning);
+        self
+    }
+
+    pub fn with_opaque_id(mut self, opaque_id: impl Into<String>) -> Self {
+        self.headers.set_opaque_id(opaque_id);
+        self
+    }
+
+    pub fn with_retry_after(mut self, delay: Duration) -> Self {
+        self.headers.set_retry_after(delay);
+        self
+    }
+}
+
+type BodyWriter = Box<dyn FnOnce(&mut dyn Write) -> io::Result<()> + Send>;
+
+// A response body produced on demand. Clones share the same producer, so the body
+// can only be written once.
+#[derive(Clone)]
+pub struct StreamingBody {
+    writer: Arc<Mutex<Option<BodyWriter>>>,
+}
+
+impl StreamingBody {
+    pub fn from_writer<F>(write: F) -> Self
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()> + Send + 'static,
+    {
+        Self {
+            writer: Arc::new(Mutex::new(Some(Box::new(write)))),
+        }
+    }
+
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator + Send + 'static,
+        I::Item: AsRef<[u8]>,
+    {
+        Self::from_writer(move |writer| {
+            for chunk in chunks {
+                writer.write_all(chunk.as_ref())?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn ndjson<I>(values: I) -> Self
+    where
+        I: IntoIterator<Item = JsonValue> + Send + 'static,
+    {
+        Self::from_writer(move |writer| {
+            for value in values {
+                writer.write_all(&value.to_bytes())?;
+                writer.write_all(b"\n")?;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        let write = self
+            .writer
+            .lock()
+            .map_err(|_| io::Error::other("response body mutex poisoned"))?
+            .take()
+            .ok_or_else(|| io::Error::other("response body was already written"))?;
+        write(writer)
+    }
+
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write_to(&mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl fmt::Debug for StreamingBody {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("StreamingBody")
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for StreamingBody {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.writer, &other.writer)
+    }
+}
+
+impl Eq for StreamingBody {}
+
+impl From<ExtensionError> for ExtensionRestResponse {
+    fn from(error: ExtensionError) -> Self {
+        Self::error(&error)
+    }
+}
+
+pub trait IntoRestResponse {
+    fn into_rest_response(self) -> ExtensionRestResponse;
+}
+
+impl IntoRestResponse for ExtensionRestResponse {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        self
+    }
+}
+
+impl IntoRestResponse for ExtensionError {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        ExtensionRestResponse::error(&self)
+    }
+}
+
+impl IntoRestResponse for String {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        ExtensionRestResponse::text(RestStatus::Ok, self)
+    }
+}
+
+impl IntoRestResponse for &'static str {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        ExtensionRestResponse::text(RestStatus::Ok, self)
+    }
+}
+
+impl IntoRestResponse for Vec<u8> {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        ExtensionRestResponse::bytes(RestStatus::Ok, self)
+    }
+}
+
+impl IntoRestResponse for StreamingBody {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        ExtensionRestResponse::streaming(
+            RestStatus::Ok,
+            ExtensionRestResponse::BINARY_CONTENT_TYPE,
+            self,
+        )
+    }
+}
+
+impl IntoRestResponse for JsonValue {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        ExtensionRestResponse::json(RestStatus::Ok, self)
+    }
+}
+
+impl<T: IntoRestResponse> IntoRestResponse for (RestStatus, T) {
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        let (status, body) = self;
+        let mut response = body.into_rest_response();
+        response.status = status;
+        response
+    }
+}
+
+impl<T, E> IntoRestResponse for Result<T, E>
+where
+    T: IntoRestResponse,
+    E: Into<ExtensionError>,
+{
+    fn into_rest_response(self) -> ExtensionRestResponse {
+        match self {
+            Ok(value) => value.into_rest_response(),
+            Err(error) => ExtensionRestResponse::error(&error.into()),
+        }
+    }
+}
+
+// Parameters that OpenSearch consumes on behalf of every handler.
+pub const RESPONSE_PARAMS: [&str; 4] = ["error_trace", "filter_path", "human", "pretty"];
+
+pub fn unrecognized_params_error<'a>(
+    path: &str,
+    params: impl IntoIterator<Item = &'a String>,
+    consumed: &BTreeSet<String>,
+) -> Option<ExtensionError> {
+    let invalid = params
+        .into_iter()
+        .filter(|key| !consumed.contains(*key) && !RESPONSE_PARAMS.contains(&key.as_str()))
+        .cloned()
+        .collect::<BTreeSet<_>>();
+    if invalid.is_empty() {
+        return None;
+    }
+
+    let candidates = consumed
+        .iter()
+        .cloned()
+        .chain(RESPONSE_PARAMS.iter().map(|key| key.to_string()))
+        .collect::<BTreeSet<_>>();
+    Some(ExtensionError::bad_request(unrecognized_params_message(
+        path,
+        &invalid,
+        &candidates,
+    )))
+}
+
+pub fn unrecognized_params_message(
+    path: &str,
+    invalid: &BTreeSet<String>,
+    candidates: &BTreeSet<String>,
+) -> String {
+    let mut message = format!(
+        "request [{path}] contains unrecognized parameter{}: ",
+        if invalid.len() > 1 { "s" } else { "" }
+    );
+
+    for (index, key) in invalid.iter().enumerate() {
+        if index > 0 {
+            message.push_str(", ");
+        }
+        message.push_str(&format!("[{key}]"));
+
+        let mut scored = candidates
+            .iter()
+            .map(|candidate| (similarity(key, candidate), candidate))
+            .filter(|(score, _)| *score > 0.5)
+            .collect::<Vec<_>>();
+        scored.sort_by(|(left_score, left), (right_score, right)| {
+            right_score
+                .total_cmp(left_score)
+                .then_with(|| left.cmp(right))
+        });
+
+        match scored.as_slice() {
+            [] => {}
+            [(_, candidate)] => message.push_str(&format!(" -> did you mean [{candidate}]?")),
+            _ => message.push_str(&format!(
+                " -> did you mean any of [{}]?",
+                scored
+                    .iter()
+                    .map(|(_, candidate)| candidate.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+
+    message
+}
+
+// Normalized Levenshtein similarity, matching Lucene's `LevenshteinDistance`.
+fn similarity(left: &str, right: &str) -> f32 {
+    let left = left.chars().collect::<Vec<_>>();
+    let right = right.chars().collect::<Vec<_>>();
+    let longest = left.len().max(right.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous = (0..=right.len()).collect::<Vec<_>>();
+    for (row, left_char) in left.iter().enumerate() {
+        let mut current = vec![row + 1; right.len() + 1];
+        for (column, right_char) in right.iter().enumerate() {
+            let substitution = previous[column] + usize::from(left_char != right_char);
+            current[column + 1] = substitution
+                .min(previous[column + 1] + 1)
+                .min(current[column] + 1);
+        }
+        previous = current;
+    }
+
+    1.0 - previous[right.len()] as f32 / longest as f32
+}
+
+// Like RestUtils.decodeComponent: `+` only means a space in query parameters, and
+// stays a literal character in path segments.
+pub fn decode_component(value: &str, is_path: bool) -> String {
+    if !value.contains('%') && (is_path || !value.contains('+')) {
+        return value.to_string();
+    }
+
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'%' if index + 2 < bytes.len() => {
+                match (hex_value(bytes[index + 1]), hex_value(bytes[index + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push(high << 4 | low);
+                        index += 3;
+                        continue;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b'+' if !is_path => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unrecognized_params_message, ExtensionRestResponse, IntoRestResponse, RestStatus};
+    use crate::error::ExtensionError;
+    use std::collections::BTreeSet;
+
+    // Declaration order of org.opensearch.core.rest.RestStatus, which defines the wire ordinals.
+    const OPENSEARCH_REST_STATUS_ORDER: [(&str, u16); 47] = [
+        ("CONTINUE", 100),
+        ("SWITCHING_PROTOCOLS", 101),
+        ("OK", 200),
+        ("CREATED", 201),
+        ("ACCEPTED", 202),
+        ("NON_AUTHORITATIVE_INFORMATION", 203),
+        ("NO_CONTENT", 204),
+        ("RESET_CONTENT", 205),
+        ("PARTIAL_CONTENT", 206),
+        ("MULTI_STATUS", 207),
+        ("MULTIPLE_CHOICES", 300),
+        ("MOVED_PERMANENTLY", 301),
+        ("FOUND", 302),
+        ("SEE_OTHER", 303),
+        ("NOT_MODIFIED", 304),
+        ("USE_PROXY", 305),
+        ("TEMPORARY_REDIRECT", 307),
+        ("BAD_REQUEST", 400),
+        ("UNAUTHORIZED", 401),
+        ("PAYMENT_REQUIRED", 402),
+        ("FORBIDDEN", 403),
+        ("NOT_FOUND", 404),
+        ("METHOD_NOT_ALLOWED", 405),
+        ("NOT_ACCEPTABLE", 406),
+        ("PROXY_AUTHENTICATION", 407),
+        ("REQUEST_TIMEOUT", 408),
+        ("CONFLICT", 409),
+        ("GONE", 410),
+        ("LENGTH_REQUIRED", 411),
+        ("PRECONDITION_FAILED", 412),
+        ("REQUEST_ENTITY_TOO_LARGE", 413),
+        ("REQUEST_URI_TOO_LONG", 414),
+        ("UNSUPPORTED_MEDIA_TYPE", 415),
+        ("REQUESTED_RANGE_NOT_SATISFIED", 416),
+        ("EXPECTATION_FAILED", 417),
+        ("MISDIRECTED_REQUEST", 421),
+        ("UNPROCESSABLE_ENTITY", 422),
+        ("LOCKED", 423),
+        ("FAILED_DEPENDENCY", 424),
+        ("TOO_MANY_REQUESTS", 429),
+        ("INTERNAL_SERVER_ERROR", 500),
+        ("NOT_IMPLEMENTED", 501),
+        ("BAD_GATEWAY", 502),
+        ("SERVICE_UNAVAILABLE", 503),
+        ("GATEWAY_TIMEOUT", 504),
+        ("HTTP_VERSION_NOT_SUPPORTED", 505),
+        ("INSUFFICIENT_STORAGE", 506),
+    ];
+
+    fn java_name(status: RestStatus) -> String {
+        let mut name = String::new();
+        for (index, character) in format!("{status:?}").chars().enumerate() {
+            if character.is_ascii_uppercase() && index > 0 {
+                name.push('_');
+            }
+            name.push(character.to_ascii_uppercase());
+        }
+        name
+    }
+
+    #[test]
+    fn rest_status_ordinals_match_opensearch_declaration_order() {
+        for (ordinal, (name, code)) in OPENSEARCH_REST_STATUS_ORDER.iter().enumerate() {
+            let status = RestStatus::from_wire(ordinal as u32).unwrap();
+            assert_eq!(java_name(status), *name);
+            assert_eq!(status.code(), *code);
+            assert_eq!(status.to_wire(), ordinal as u32);
+        }
+        assert!(RestStatus::from_wire(OPENSEARCH_REST_STATUS_ORDER.len() as u32).is_err());
+    }
+
+    #[test]
+    fn rest_status_converts_to_and_from_http_codes() {
+        assert_eq!(
+            RestStatus::from_code(429),
+            Some(RestStatus::TooManyRequests)
+        );
+        assert_eq!(RestStatus::from_code(418), None);
+        assert_eq!(u16::from(RestStatus::ServiceUnavailable), 503);
+        assert_eq!(RestStatus::try_from(404).unwrap(), RestStatus::NotFound);
+        assert!(RestStatus::Created.is_success());
+        assert!(RestStatus::Conflict.is_client_error());
+        assert!(RestStatus::BadGateway.is_server_error());
+    }
+
+    fn set(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn unrecognized_params_message_suggests_close_candidates() {
+        assert_eq!(
+            unrecognized_params_message("/items", &set(&["sise"]), &set(&["size", "from"])),
+            "request [/items] contains unrecognized parameter: [sise] -> did you mean [size]?"
+        );
+        assert_eq!(
+            unrecognized_params_message(
+                "/items",
+                &set(&["filter", "zzz"]),
+                &set(&["filters", "filter_path", "pretty"])
+            ),
+            "request [/items] contains unrecognized parameters: [filter] -> did you mean any of [filters, filter_path]?, [zzz]"
+        );
+    }
+
+    #[test]
+    fn responders_build_responses_from_plain_values() {
+        let text = "hello".into_rest_response();
+        assert_eq!(text.status, RestStatus::Ok);
+        assert_eq!(text.content_type, ExtensionRestResponse::TEXT_CONTENT_TYPE);
+
+        let bytes = (RestStatus::Accepted, vec![1u8, 2]).into_rest_response();
+        assert_eq!(bytes.status, RestStatus::Accepted);
+        assert_eq!(
+            bytes.content_type,
+            ExtensionRestResponse::BINARY_CONTENT_TYPE
+        );
+        assert_eq!(bytes.content, vec![1, 2]);
+
+        let failed: Result<String, ExtensionError> = Err(ExtensionError::rejected("queue is full"));
+        let failed = failed.into_rest_response();
+        assert_eq!(failed.status, RestStatus::TooManyRequests);
+        assert_eq!(
+            failed.content_type,
+            ExtensionRestResponse::JSON_CONTENT_TYPE
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_bodies_round_trip_and_report_bad_requests() {
+        use super::{ExtensionRestRequest, ExtensionRestResponse, HttpVersion, RestMethod};
+        use crate::header::HeaderMap;
+        use crate::json::JsonValue;
+        use std::collections::BTreeMap;
+
+        #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+        struct Greeting {
+            name: String,
+            count: u32,
+        }
+
+        let mut request = ExtensionRestRequest::new(
+            RestMethod::Post,
+            "/greet".into(),
+            "/greet".into(),
+            BTreeMap::new(),
+            HeaderMap::new(),
+            None,
+            br#"{"name":"rust","count":2}"#.to_vec(),
+            String::new(),
+            HttpVersion::Http11,
+        );
+        let greeting = request.parse_body::<Greeting>().unwrap();
+        assert!(request.content_consumed());
+        assert_eq!(
+            greeting,
+            Greeting {
+                name: "rust".into(),
+                count: 2
+            }
+        );
+
+        let response = ExtensionRestResponse::json_of(RestStatus::Ok, &greeting);
+        assert_eq!(
+            response.content_type,
+            ExtensionRestResponse::JSON_CONTENT_TYPE
+        );
+        assert_eq!(response.content, br#"{"name":"rust","count":2}"#);
+
+        request.content = br#"{"name":"rust"}"#.to_vec();
+        let rejected = ExtensionRestResponse::from(request.parse_body::<Greeting>().unwrap_err());
+        assert_eq!(rejected.status, RestStatus::BadRequest);
+        let body = JsonValue::parse_bytes(&rejected.content).unwrap();
+        assert_eq!(body.get("status").and_then(JsonValue::as_i64), Some(400));
+        assert_eq!(
+            body.get("error")
+                .and_then(|error| error.get("reason"))
+                .and_then(JsonValue::as_str),
+            Some("failed to parse request body: missing field `count` at line 1 column 15")
+        );
+    }
 }
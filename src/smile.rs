@@ -0,0 +1,376 @@
+use crate::json::JsonValue;
+use crate::xcontent::XContentError;
+use std::collections::BTreeMap;
+
+// Jackson's binary JSON format, as used by OpenSearch's SmileXContent.
+const HEADER: [u8; 3] = [b':', b')', b'\n'];
+const FLAG_SHARED_NAMES: u8 = 0x01;
+const FLAG_SHARED_VALUES: u8 = 0x02;
+const END_OF_STRING: u8 = 0xfc;
+const END_OF_CONTENT: u8 = 0xff;
+const MAX_SHARED_ENTRIES: usize = 1024;
+const MAX_SHARED_VALUE_BYTES: usize = 64;
+const MAX_DEPTH: usize = 128;
+
+pub fn decode(input: &[u8]) -> Result<JsonValue, XContentError> {
+    let mut decoder = Decoder {
+        input,
+        position: 0,
+        shared_names: None,
+        shared_values: None,
+    };
+    decoder.read_header()?;
+    let value = decoder.read_value(0)?;
+    if decoder.input.get(decoder.position) == Some(&END_OF_CONTENT) {
+        decoder.position += 1;
+    }
+    if decoder.position < input.len() {
+        return Err(decoder.error("unexpected trailing bytes"));
+    }
+    Ok(value)
+}
+
+// Output never uses back-references, so any conforming reader can consume it.
+pub fn encode(value: &JsonValue) -> Vec<u8> {
+    let mut output = HEADER.to_vec();
+    output.push(0x00);
+    write_value(value, &mut output);
+    output
+}
+
+struct Decoder<'a> {
+    input: &'a [u8],
+    position: usize,
+    shared_names: Option<Vec<String>>,
+    shared_values: Option<Vec<String>>,
+}
+
+impl Decoder<'_> {
+    fn error(&self, message: &str) -> XContentError {
+        XContentError::new(format!("{message} at offset {}", self.position))
+    }
+
+    fn next(&mut self) -> Result<u8, XContentError> {
+        let byte = *self
+            .input
+            .get(self.position)
+            .ok_or_else(|| self.error("unexpected end of SMILE input"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8], XContentError> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.input.len())
+            .ok_or_else(|| self.error("unexpected end of SMILE input"))?;
+        let bytes = &self.input[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn read_header(&mut self) -> Result<(), XContentError> {
+        if !self.input.starts_with(&HEADER) {
+            return Err(self.error("missing SMILE header"));
+        }
+        self.position = HEADER.len();
+        let flags = self.next()?;
+        if flags >> 4 != 0 {
+            return Err(self.error("unsupported SMILE version"));
+        }
+        self.shared_names = (flags & FLAG_SHARED_NAMES != 0).then(Vec::new);
+        self.shared_values = (flags & FLAG_SHARED_VALUES != 0).then(Vec::new);
+        Ok(())
+    }
+
+    fn read_text(&mut self, length: usize) -> Result<String, XContentError> {
+        let bytes = self.take(length)?.to_vec();
+        String::from_utf8(bytes).map_err(|_| self.error("SMILE text is not valid UTF-8"))
+    }
+
+    fn read_terminated_text(&mut self) -> Result<String, XContentError> {
+        let length = self.input[self.position..]
+            .iter()
+            .position(|byte| *byte == END_OF_STRING)
+            .ok_or_else(|| self.error("unterminated SMILE string"))?;
+        let text = self.read_text(length)?;
+        self.position += 1;
+        Ok(text)
+    }
+
+    fn read_vint(&mut self) -> Result<u64, XContentError> {
+        let mut value = 0u64;
+        for _ in 0..10 {
+            let byte = self.next()?;
+            if byte & 0x80 != 0 {
+                return Ok((value << 6) | u64::from(byte & 0x3f));
+            }
+            value = (value << 7) | u64::from(byte);
+        }
+        Err(self.error("SMILE variable-length integer is too long"))
+    }
+
+    fn read_seven_bit(&mut self, bytes: usize) -> Result<u64, XContentError> {
+        Ok(self
+            .take(bytes)?
+            .iter()
+            .fold(0u64, |value, byte| (value << 7) | u64::from(byte & 0x7f)))
+    }
+
+    fn shared(&self, table: &Option<Vec<String>>, index: usize) -> Result<String, XContentError> {
+        table
+            .as_ref()
+            .and_then(|table| table.get(index))
+            .cloned()
+            .ok_or_else(|| self.error("invalid SMILE shared string reference"))
+    }
+
+    fn remember_value(&mut self, value: &str) {
+        if value.len() > MAX_SHARED_VALUE_BYTES {
+            return;
+        }
+        if let Some(values) = &mut self.shared_values {
+            remember(values, value);
+        }
+    }
+
+    fn read_value(&mut self, depth: usize) -> Result<JsonValue, XContentError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("SMILE input is nested too deeply"));
+        }
+
+        let token = self.next()?;
+        match token {
+            0x01..=0x1f => {
+                let value = self.shared(&self.shared_values, usize::from(token - 1))?;
+                Ok(JsonValue::String(value))
+            }
+            0xec..=0xef => {
+                let index = (usize::from(token & 0x03) << 8) | usize::from(self.next()?);
+                let value = self.shared(&self.shared_values, index)?;
+                Ok(JsonValue::String(value))
+            }
+            0x20 => Ok(JsonValue::String(String::new())),
+            0x21 => Ok(JsonValue::Null),
+            0x22 => Ok(JsonValue::Bool(false)),
+            0x23 => Ok(JsonValue::Bool(true)),
+            0x24 => Ok(JsonValue::Integer(i64::from(
+                zigzag_decode(self.read_vint()?) as i32,
+            ))),
+            0x25 => Ok(JsonValue::Integer(zigzag_decode(self.read_vint()?))),
+            0x28 => {
+                let bits = self.read_seven_bit(5)? as u32;
+                Ok(JsonValue::Float(f32::from_bits(bits).into()))
+            }
+            0x29 => Ok(JsonValue::Float(f64::from_bits(self.read_seven_bit(10)?))),
+            0x40..=0xbf => {
+                let length = match token {
+                    0x40..=0x5f => usize::from(token & 0x1f) + 1,
+                    0x60..=0x7f => usize::from(token & 0x1f) + 33,
+                    0x80..=0x9f => usize::from(token & 0x1f) + 2,
+                    _ => usize::from(token & 0x1f) + 34,
+                };
+                let value = self.read_text(length)?;
+                self.remember_value(&value);
+                Ok(JsonValue::String(value))
+            }
+            0xc0..=0xdf => Ok(JsonValue::Integer(zigzag_decode(u64::from(token & 0x1f)))),
+            0xe0 | 0xe4 => self.read_terminated_text().map(JsonValue::String),
+            0xf8 => {
+                let mut values = Vec::new();
+                while self.input.get(self.position) != Some(&0xf9) {
+                    values.push(self.read_value(depth + 1)?);
+                }
+                self.position += 1;
+                Ok(JsonValue::Array(values))
+            }
+            0xfa => {
+                let mut entries = BTreeMap::new();
+                while let Some(key) = self.read_key()? {
+                    if entries.contains_key(&key) {
+                        return Err(self.error(&format!("duplicate field name [{key}]")));
+                    }
+                    entries.insert(key, self.read_value(depth + 1)?);
+                }
+                Ok(JsonValue::Object(entries))
+            }
+            _ => Err(self.error("unsupported SMILE value token")),
+        }
+    }
+
+    // Returns None at the end of an object.
+    fn read_key(&mut self) -> Result<Option<String>, XContentError> {
+        let token = self.next()?;
+        let key = match token {
+            0xfb => return Ok(None),
+            0x20 => return Ok(Some(String::new())),
+            0x30..=0x33 => {
+                let index = (usize::from(token & 0x03) << 8) | usize::from(self.next()?);
+                return self.shared(&self.shared_names, index).map(Some);
+            }
+            0x40..=0x7f => {
+                return self
+                    .shared(&self.shared_names, usize::from(token & 0x3f))
+                    .map(Some)
+            }
+            0x34 => self.read_terminated_text()?,
+            0x80..=0xbf => self.read_text(usize::from(token & 0x3f) + 1)?,
+            0xc0..=0xf7 => self.read_text(usize::from(token & 0x3f) + 2)?,
+            _ => return Err(self.error("unsupported SMILE key token")),
+        };
+        if let Some(names) = &mut self.shared_names {
+            remember(names, &key);
+        }
+        Ok(Some(key))
+    }
+}
+
+fn remember(table: &mut Vec<String>, value: &str) {
+    if table.len() == MAX_SHARED_ENTRIES {
+        table.clear();
+    }
+    table.push(value.to_string());
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn write_vint(value: u64, output: &mut Vec<u8>) {
+    let mut groups = vec![(value & 0x3f) as u8 | 0x80];
+    let mut rest = value >> 6;
+    while rest > 0 {
+        groups.push((rest & 0x7f) as u8);
+        rest >>= 7;
+    }
+    output.extend(groups.iter().rev());
+}
+
+fn write_value(value: &JsonValue, output: &mut Vec<u8>) {
+    match value {
+        JsonValue::Null => output.push(0x21),
+        JsonValue::Bool(false) => output.push(0x22),
+        JsonValue::Bool(true) => output.push(0x23),
+        JsonValue::Integer(value) if (-16..=15).contains(value) => {
+            output.push(0xc0 | zigzag_encode(*value) as u8);
+        }
+        JsonValue::Integer(value) if i32::try_from(*value).is_ok() => {
+            output.push(0x24);
+            write_vint(zigzag_encode(*value) & 0xffff_ffff, output);
+        }
+        JsonValue::Integer(value) => {
+            output.push(0x25);
+            write_vint(zigzag_encode(*value), output);
+        }
+        JsonValue::Float(value) => {
+            output.push(0x29);
+            let bits = value.to_bits();
+            for shift in (0..10).rev() {
+                output.push(((bits >> (shift * 7)) & 0x7f) as u8);
+            }
+        }
+        JsonValue::String(value) => write_string(value, output),
+        JsonValue::Array(values) => {
+            output.push(0xf8);
+            for value in values {
+                write_value(value, output);
+            }
+            output.push(0xf9);
+        }
+        JsonValue::Object(entries) => {
+            output.push(0xfa);
+            for (key, value) in entries {
+                write_key(key, output);
+                write_value(value, output);
+            }
+            output.push(0xfb);
+        }
+    }
+}
+
+fn write_string(value: &str, output: &mut Vec<u8>) {
+    let length = value.len();
+    let token = match (value.is_ascii(), length) {
+        (_, 0) => return output.push(0x20),
+        (true, 1..=32) => 0x40 + (length - 1) as u8,
+        (true, 33..=64) => 0x60 + (length - 33) as u8,
+        (false, 2..=33) => 0x80 + (length - 2) as u8,
+        (false, 34..=65) => 0xa0 + (length - 34) as u8,
+        (true, _) => 0xe0,
+        (false, _) => 0xe4,
+    };
+    output.push(token);
+    output.extend_from_slice(value.as_bytes());
+    if token >= 0xe0 {
+        output.push(END_OF_STRING);
+    }
+}
+
+fn write_key(key: &str, output: &mut Vec<u8>) {
+    let length = key.len();
+    let token = match (key.is_ascii(), length) {
+        (_, 0) => return output.push(0x20),
+        (true, 1..=64) => 0x80 + (length - 1) as u8,
+        (false, 2..=57) => 0xc0 + (length - 2) as u8,
+        _ => 0x34,
+    };
+    output.push(token);
+    output.extend_from_slice(key.as_bytes());
+    if token == 0x34 {
+        output.push(END_OF_STRING);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::json::JsonValue;
+
+    #[test]
+    fn decodes_jackson_output_with_shared_names() {
+        // Jackson's SmileGenerator output for [{"a":1},{"a":"é"}] with shared names enabled.
+        let bytes = [
+            b':', b')', b'\n', 0x01, 0xf8, 0xfa, 0x80, b'a', 0xc2, 0xfb, 0xfa, 0x40, 0x80, 0xc3,
+            0xa9, 0xfb, 0xf9, 0xff,
+        ];
+        assert_eq!(
+            decode(&bytes).unwrap(),
+            JsonValue::parse(r#"[{"a":1},{"a":"é"}]"#).unwrap()
+        );
+        assert!(decode(&bytes[3..]).is_err());
+    }
+
+    #[test]
+    fn encoded_documents_round_trip() {
+        let document = JsonValue::parse(&format!(
+            r#"{{"small":-3,"int":70000,"long":-9000000000,"ratio":0.1,"flags":[true,false,null],
+                "ascii":"{}","unicode":"héllo","empty":"","{}":"long key"}}"#,
+            "x".repeat(80),
+            "k".repeat(70)
+        ))
+        .unwrap();
+        assert_eq!(decode(&encode(&document)).unwrap(), document);
+
+        // {"a":1,"a":2} without shared names.
+        let duplicate = [
+            0x3a, 0x29, 0x0a, 0x00, 0xfa, 0x80, b'a', 0xc2, 0x80, b'a', 0xc4, 0xfb,
+        ];
+        assert!(decode(&duplicate)
+            .unwrap_err()
+            .to_string()
+            .starts_with("duplicate field name [a]"));
+        assert!(decode(
+            &duplicate[..8]
+                .iter()
+                .chain(&[0xfb])
+                .copied()
+                .collect::<Vec<_>>()
+        )
+        .is_ok());
+    }
+}
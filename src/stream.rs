@@ -1,5 +1,5 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::io::{self, Cursor, Read};
+use std::io::{self, Cursor, Read, Write};
 
 #[derive(Debug, Clone)]
 pub struct StreamInput<'a> {
@@ -193,8 +193,50 @@ impl StreamOutput {
         self.write_bytes(value);
     }
 
+    // Writes a length-prefixed byte array whose length is only known once `write`
+    // returns. Room for the prefix is reserved up front, wide enough for `limit`, and
+    // the length is filled in afterwards as a padded vint, which readVInt accepts, so
+    // the body is written once and never moved. Fails with `FileTooLarge` once more
+    // than `limit` bytes are written.
+    pub fn write_byte_array_with<F>(&mut self, limit: usize, write: F) -> io::Result<()>
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()>,
+    {
+        let capacity = limit.min(i32::MAX as usize);
+        let width = vint_len(capacity as u32);
+        let start = self.bytes.len();
+        self.bytes.resize(start + width, 0);
+        let mut writer = LimitedWriter {
+            bytes: &mut self.bytes,
+            remaining: capacity,
+            exceeded: false,
+        };
+        let result = write(&mut writer);
+        let exceeded = writer.exceeded;
+        if exceeded || result.is_err() {
+            self.bytes.truncate(start);
+        }
+        if exceeded {
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                format!("byte array exceeds [{limit}] bytes"),
+            ));
+        }
+        result?;
+
+        let mut length = (self.bytes.len() - start - width) as u32;
+        for (index, byte) in self.bytes[start..start + width].iter_mut().enumerate() {
+            *byte = (length & 0x7F) as u8;
+            if index + 1 < width {
+                *byte |= 0x80;
+            }
+            length >>= 7;
+        }
+        Ok(())
+    }
+
     pub fn write_string(&mut self, value: &str) {
-        self.write_vint(value.as_bytes().len() as u32);
+        self.write_vint(value.len() as u32);
         self.write_bytes(value.as_bytes());
     }
 
@@ -231,6 +273,35 @@ impl StreamOutput {
     }
 }
 
+fn vint_len(value: u32) -> usize {
+    (32 - value.leading_zeros() as usize).div_ceil(7).max(1)
+}
+
+struct LimitedWriter<'a> {
+    bytes: &'a mut Vec<u8>,
+    remaining: usize,
+    exceeded: bool,
+}
+
+impl Write for LimitedWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.remaining {
+            self.exceeded = true;
+            return Err(io::Error::new(
+                io::ErrorKind::FileTooLarge,
+                "byte array size limit exceeded",
+            ));
+        }
+        self.remaining -= buf.len();
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{StreamInput, StreamOutput};
@@ -251,4 +322,43 @@ mod tests {
             assert_eq!(input.read_vint().unwrap(), value);
         }
     }
+
+    #[test]
+    fn streamed_byte_arrays_match_buffered_ones_and_respect_the_limit() {
+        let body = vec![7u8; 300];
+        let mut buffered = StreamOutput::new();
+        buffered.write_u8(1);
+        buffered.write_byte_array(&body);
+
+        let mut streamed = StreamOutput::new();
+        streamed.write_u8(1);
+        streamed
+            .write_byte_array_with(300, |writer| {
+                for chunk in body.chunks(64) {
+                    writer.write_all(chunk)?;
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(streamed.into_bytes(), buffered.into_bytes());
+
+        let mut limited = StreamOutput::new();
+        let error = limited
+            .write_byte_array_with(299, |writer| writer.write_all(&body))
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::FileTooLarge);
+        assert!(limited.into_bytes().is_empty());
+
+        // Short bodies keep the prefix width reserved for the limit.
+        let mut padded = StreamOutput::new();
+        padded
+            .write_byte_array_with(300, |writer| writer.write_all(b"abc"))
+            .unwrap();
+        padded.write_u8(9);
+        let bytes = padded.into_bytes();
+        assert_eq!(bytes.len(), 2 + 3 + 1);
+        let mut input = StreamInput::new(&bytes);
+        assert_eq!(input.read_byte_array().unwrap(), b"abc");
+        assert_eq!(input.read_u8().unwrap(), 9);
+    }
 }
@@ -0,0 +1,550 @@
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClientAuth {
+    #[default]
+    None,
+    Optional,
+    Required,
+}
+
+impl ClientAuth {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "optional" => Some(Self::Optional),
+            "required" => Some(Self::Required),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Optional => "optional",
+            Self::Required => "required",
+        }
+    }
+}
+
+// Certificate, key and CA paths point at PEM files. The CA verifies client
+// certificates on the listener and server certificates on outbound connections.
+// Outbound connections only present a certificate when a client certificate is set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+    pub certificate_path: PathBuf,
+    pub private_key_path: PathBuf,
+    pub ca_path: Option<PathBuf>,
+    pub client_auth: ClientAuth,
+    pub verify_hostname: bool,
+    pub client_certificate_path: Option<PathBuf>,
+    pub client_private_key_path: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    pub fn new(certificate_path: impl Into<PathBuf>, private_key_path: impl Into<PathBuf>) -> Self {
+        Self {
+            certificate_path: certificate_path.into(),
+            private_key_path: private_key_path.into(),
+            ca_path: None,
+            client_auth: ClientAuth::None,
+            verify_hostname: true,
+            client_certificate_path: None,
+            client_private_key_path: None,
+        }
+    }
+
+    pub fn with_ca_path(mut self, ca_path: impl Into<PathBuf>) -> Self {
+        self.ca_path = Some(ca_path.into());
+        self
+    }
+
+    pub fn with_client_auth(mut self, client_auth: ClientAuth) -> Self {
+        self.client_auth = client_auth;
+        self
+    }
+
+    pub fn with_hostname_verification(mut self, verify_hostname: bool) -> Self {
+        self.verify_hostname = verify_hostname;
+        self
+    }
+
+    pub fn with_client_certificate(
+        mut self,
+        certificate_path: impl Into<PathBuf>,
+        private_key_path: impl Into<PathBuf>,
+    ) -> Self {
+        self.client_certificate_path = Some(certificate_path.into());
+        self.client_private_key_path = Some(private_key_path.into());
+        self
+    }
+}
+
+#[cfg(feature = "tls")]
+pub use rustls_support::{TlsContext, TlsReader, TlsStream, TlsWriter};
+
+#[cfg(feature = "tls")]
+mod rustls_support {
+    use super::{ClientAuth, TlsConfig};
+    use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+    use rustls::client::WebPkiServerVerifier;
+    use rustls::crypto::ring;
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+    use rustls::server::WebPkiClientVerifier;
+    use rustls::{
+        CertificateError, ClientConfig, ClientConnection, Connection, DigitallySignedStruct,
+        RootCertStore, ServerConfig, ServerConnection, SignatureScheme,
+    };
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::net::TcpStream;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex, MutexGuard};
+
+    const READ_BUFFER_SIZE: usize = 16 * 1024;
+
+    pub struct TlsContext {
+        server: Arc<ServerConfig>,
+        // Outbound connections need a CA to verify the server against.
+        client: Option<Arc<ClientConfig>>,
+    }
+
+    impl TlsContext {
+        pub fn new(config: &TlsConfig) -> io::Result<Self> {
+            let provider = Arc::new(ring::default_provider());
+            let certificates = load_certificates(&config.certificate_path)?;
+            let private_key = PrivateKeyDer::from_pem_file(&config.private_key_path)
+                .map_err(|error| pem_error(&config.private_key_path, error))?;
+            let roots = match &config.ca_path {
+                Some(ca_path) => {
+                    let mut roots = RootCertStore::empty();
+                    for certificate in load_certificates(ca_path)? {
+                        roots.add(certificate).map_err(tls_error)?;
+                    }
+                    Some(Arc::new(roots))
+                }
+                None => None,
+            };
+
+            let server = ServerConfig::builder_with_provider(Arc::clone(&provider))
+                .with_safe_default_protocol_versions()
+                .map_err(tls_error)?;
+            let server = match (config.client_auth, &roots) {
+                (ClientAuth::None, _) => server.with_no_client_auth(),
+                (client_auth, Some(roots)) => {
+                    let verifier = WebPkiClientVerifier::builder_with_provider(
+                        Arc::clone(roots),
+                        Arc::clone(&provider),
+                    );
+                    let verifier = match client_auth {
+                        ClientAuth::Optional => verifier.allow_unauthenticated(),
+                        _ => verifier,
+                    };
+                    server.with_client_cert_verifier(verifier.build().map_err(tls_error)?)
+                }
+                (client_auth, None) => {
+                    return Err(io::Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "client auth [{}] requires a CA certificate path",
+                            client_auth.as_str()
+                        ),
+                    ))
+                }
+            }
+            .with_single_cert(certificates, private_key)
+            .map_err(tls_error)?;
+
+            let client = match roots {
+                Some(roots) => {
+                    let verifier =
+                        WebPkiServerVerifier::builder_with_provider(roots, Arc::clone(&provider))
+                            .build()
+                            .map_err(tls_error)?;
+                    let client = ClientConfig::builder_with_provider(provider)
+                        .with_safe_default_protocol_versions()
+                        .map_err(tls_error)?
+                        .dangerous()
+                        .with_custom_certificate_verifier(Arc::new(ServerVerifier {
+                            inner: verifier,
+                            verify_hostname: config.verify_hostname,
+                        }));
+                    let client = match (
+                        &config.client_certificate_path,
+                        &config.client_private_key_path,
+                    ) {
+                        (Some(certificate_path), Some(private_key_path)) => {
+                            let private_key = PrivateKeyDer::from_pem_file(private_key_path)
+                                .map_err(|error| pem_error(private_key_path, error))?;
+                            client
+                                .with_client_auth_cert(
+                                    load_certificates(certificate_path)?,
+                                    private_key,
+                                )
+                                .map_err(tls_error)?
+                        }
+                        _ => client.with_no_client_auth(),
+                    };
+                    Some(Arc::new(client))
+                }
+                None => None,
+            };
+
+            Ok(Self {
+                server: Arc::new(server),
+                client,
+            })
+        }
+
+        pub fn accept(&self, stream: TcpStream) -> io::Result<TlsStream> {
+            let connection = ServerConnection::new(Arc::clone(&self.server)).map_err(tls_error)?;
+            TlsStream::new(stream, connection.into())
+        }
+
+        // The handshake completes before this returns: a client usually writes first,
+        // and writes cannot make progress while the handshake waits on a reader.
+        pub fn connect(&self, mut stream: TcpStream, server_name: &str) -> io::Result<TlsStream> {
+            let server_name = ServerName::try_from(server_name.to_string())
+                .map_err(|error| io::Error::new(ErrorKind::InvalidInput, error))?;
+            let Some(client) = &self.client else {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    "outbound TLS connections require a CA certificate path",
+                ));
+            };
+            let mut connection =
+                ClientConnection::new(Arc::clone(client), server_name).map_err(tls_error)?;
+            while connection.is_handshaking() {
+                connection.complete_io(&mut stream)?;
+            }
+            TlsStream::new(stream, connection.into())
+        }
+    }
+
+    // The reader and writer halves share one TLS session, so a connection can be read
+    // on one thread while REST responses are written from worker threads. The session
+    // lock is never held while blocking on the socket read.
+    struct Session {
+        connection: Connection,
+        socket: TcpStream,
+    }
+
+    impl Session {
+        fn flush_tls(&mut self) -> io::Result<()> {
+            while self.connection.wants_write() {
+                self.connection.write_tls(&mut self.socket)?;
+            }
+            Ok(())
+        }
+    }
+
+    type SharedSession = Arc<Mutex<Session>>;
+
+    fn lock(session: &SharedSession) -> io::Result<MutexGuard<'_, Session>> {
+        session
+            .lock()
+            .map_err(|_| io::Error::other("TLS session mutex poisoned"))
+    }
+
+    pub struct TlsStream {
+        reader: TlsReader,
+        writer: TlsWriter,
+    }
+
+    impl TlsStream {
+        fn new(socket: TcpStream, connection: Connection) -> io::Result<Self> {
+            let reader_socket = socket.try_clone()?;
+            let session = Arc::new(Mutex::new(Session { connection, socket }));
+            Ok(Self {
+                reader: TlsReader {
+                    session: Arc::clone(&session),
+                    socket: reader_socket,
+                },
+                writer: TlsWriter { session },
+            })
+        }
+
+        pub fn split(self) -> (TlsReader, TlsWriter) {
+            (self.reader, self.writer)
+        }
+    }
+
+    impl Read for TlsStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reader.read(buf)
+        }
+    }
+
+    impl Write for TlsStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writer.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.writer.flush()
+        }
+    }
+
+    pub struct TlsReader {
+        session: SharedSession,
+        socket: TcpStream,
+    }
+
+    impl Read for TlsReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let mut incoming = [0u8; READ_BUFFER_SIZE];
+            loop {
+                {
+                    let mut session = lock(&self.session)?;
+                    match session.connection.reader().read(buf) {
+                        Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+                        result => return result,
+                    }
+                    session.flush_tls()?;
+                }
+
+                let read = self.socket.read(&mut incoming)?;
+                if read == 0 {
+                    return Ok(0);
+                }
+
+                let mut session = lock(&self.session)?;
+                let mut pending = &incoming[..read];
+                while !pending.is_empty() {
+                    session.connection.read_tls(&mut pending)?;
+                    if let Err(error) = session.connection.process_new_packets() {
+                        // Let the peer know why the handshake or record was rejected.
+                        let _ = session.flush_tls();
+                        return Err(io::Error::new(ErrorKind::InvalidData, error));
+                    }
+                }
+                session.flush_tls()?;
+            }
+        }
+    }
+
+    pub struct TlsWriter {
+        session: SharedSession,
+    }
+
+    impl Write for TlsWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let mut session = lock(&self.session)?;
+            // rustls buffers a bounded amount of plaintext and accepts nothing once it
+            // is full, so drain it to the socket before taking more.
+            if session.connection.wants_write() {
+                session.flush_tls()?;
+            }
+            session.connection.writer().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            lock(&self.session)?.flush_tls()
+        }
+    }
+
+    // Delegates to webpki but, when hostname verification is off, accepts a
+    // certificate whose only fault is not naming the host that was dialed.
+    #[derive(Debug)]
+    struct ServerVerifier {
+        inner: Arc<WebPkiServerVerifier>,
+        verify_hostname: bool,
+    }
+
+    impl ServerCertVerifier for ServerVerifier {
+        fn verify_server_cert(
+            &self,
+            end_entity: &CertificateDer<'_>,
+            intermediates: &[CertificateDer<'_>],
+            server_name: &ServerName<'_>,
+            ocsp_response: &[u8],
+            now: UnixTime,
+        ) -> Result<ServerCertVerified, rustls::Error> {
+            match self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                ocsp_response,
+                now,
+            ) {
+                Err(rustls::Error::InvalidCertificate(
+                    CertificateError::NotValidForName
+                    | CertificateError::NotValidForNameContext { .. },
+                )) if !self.verify_hostname => Ok(ServerCertVerified::assertion()),
+                result => result,
+            }
+        }
+
+        fn verify_tls12_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls12_signature(message, cert, dss)
+        }
+
+        fn verify_tls13_signature(
+            &self,
+            message: &[u8],
+            cert: &CertificateDer<'_>,
+            dss: &DigitallySignedStruct,
+        ) -> Result<HandshakeSignatureValid, rustls::Error> {
+            self.inner.verify_tls13_signature(message, cert, dss)
+        }
+
+        fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+            self.inner.supported_verify_schemes()
+        }
+    }
+
+    fn load_certificates(path: &Path) -> io::Result<Vec<CertificateDer<'static>>> {
+        let certificates = CertificateDer::pem_file_iter(path)
+            .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+            .map_err(|error| pem_error(path, error))?;
+        if certificates.is_empty() {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                format!("no certificates found in [{}]", path.display()),
+            ));
+        }
+        Ok(certificates)
+    }
+
+    fn pem_error(path: &Path, error: rustls::pki_types::pem::Error) -> io::Error {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("failed to read PEM file [{}]: {error}", path.display()),
+        )
+    }
+
+    fn tls_error(error: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+        io::Error::new(ErrorKind::InvalidData, error)
+    }
+}
+
+#[cfg(all(test, feature = "tls"))]
+mod tests {
+    use super::{ClientAuth, TlsConfig, TlsContext};
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::thread;
+
+    fn cert(name: &str) -> String {
+        format!("{}/tests/certs/{name}", env!("CARGO_MANIFEST_DIR"))
+    }
+
+    fn server_config() -> TlsConfig {
+        TlsConfig::new(cert("server.pem"), cert("server.key")).with_ca_path(cert("ca.pem"))
+    }
+
+    fn client_config() -> TlsConfig {
+        server_config().with_client_certificate(cert("client.pem"), cert("client.key"))
+    }
+
+    // Accepts one connection, echoes one line back and reports whether the handshake held.
+    fn echo_once(config: TlsConfig) -> (u16, thread::JoinHandle<bool>) {
+        let context = TlsContext::new(&config).unwrap();
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = context.accept(stream).unwrap();
+            let mut buf = [0u8; 5];
+            if stream.read_exact(&mut buf).is_err() {
+                return false;
+            }
+            stream.write_all(&buf).is_ok() && stream.flush().is_ok()
+        });
+        (port, server)
+    }
+
+    fn exchange(config: TlsConfig, server_name: &str, port: u16) -> std::io::Result<Vec<u8>> {
+        let context = TlsContext::new(&config)?;
+        let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port))?;
+        let mut stream = context.connect(stream, server_name)?;
+        stream.write_all(b"hello")?;
+        stream.flush()?;
+        let mut buf = vec![0u8; 5];
+        stream.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    #[test]
+    fn mutual_tls_round_trips_with_the_test_ca() {
+        let (port, server) = echo_once(server_config().with_client_auth(ClientAuth::Required));
+        assert_eq!(
+            exchange(client_config(), "localhost", port).unwrap(),
+            b"hello"
+        );
+        assert!(server.join().unwrap());
+
+        // The listener's own certificate is not presented as a client certificate.
+        let (port, server) = echo_once(server_config().with_client_auth(ClientAuth::Required));
+        assert!(exchange(server_config(), "localhost", port).is_err());
+        assert!(!server.join().unwrap());
+
+        // The client certificate is not signed for serving, and no other CA is trusted.
+        let untrusted =
+            TlsConfig::new(cert("client.pem"), cert("client.key")).with_ca_path(cert("client.pem"));
+        let (port, server) = echo_once(server_config());
+        assert!(exchange(untrusted, "localhost", port).is_err());
+        assert!(!server.join().unwrap());
+    }
+
+    #[test]
+    fn hostname_verification_can_be_disabled() {
+        let (port, server) = echo_once(server_config());
+        assert!(exchange(client_config(), "opensearch.example", port).is_err());
+        assert!(!server.join().unwrap());
+
+        let (port, server) = echo_once(server_config());
+        let lenient = client_config().with_hostname_verification(false);
+        assert_eq!(
+            exchange(lenient, "opensearch.example", port).unwrap(),
+            b"hello"
+        );
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn writes_larger_than_the_tls_buffer_go_through_whole() {
+        let frame = (0..256 * 1024).map(|index| index as u8).collect::<Vec<_>>();
+        let expected = frame.clone();
+        let context = TlsContext::new(&server_config()).unwrap();
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut stream = context.accept(stream).unwrap();
+            let mut buf = vec![0u8; expected.len()];
+            stream.read_exact(&mut buf).unwrap();
+            buf == expected
+        });
+
+        let context = TlsContext::new(&client_config()).unwrap();
+        let stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        let mut stream = context.connect(stream, "localhost").unwrap();
+        stream.write_all(&frame).unwrap();
+        stream.flush().unwrap();
+        assert!(server.join().unwrap());
+    }
+
+    #[test]
+    fn invalid_settings_are_reported_up_front() {
+        let config = TlsConfig::new(cert("server.pem"), cert("server.key"))
+            .with_client_auth(ClientAuth::Required);
+        assert!(TlsContext::new(&config).is_err());
+        let server_only = TlsContext::new(&TlsConfig::new(cert("server.pem"), cert("server.key")));
+        let stream = TcpStream::connect(
+            TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+                .unwrap()
+                .local_addr()
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(server_only.unwrap().connect(stream, "localhost").is_err());
+        assert!(TlsContext::new(&TlsConfig::new(cert("missing.pem"), cert("server.key"))).is_err());
+        assert_eq!(ClientAuth::parse("Optional"), Some(ClientAuth::Optional));
+    }
+}
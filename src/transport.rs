@@ -1,3 +1,5 @@
+use crate::error::ExtensionError;
+use crate::header::HeaderMap;
 use crate::rest::{ExtensionRestRequest, ExtensionRestResponse, HttpVersion, RestMethod};
 use crate::stream::{StreamInput, StreamOutput};
 use std::collections::{BTreeMap, BTreeSet};
@@ -77,6 +79,8 @@ impl fmt::Display for Version {
 pub struct ThreadContext {
     pub request_headers: BTreeMap<String, String>,
     pub response_headers: BTreeMap<String, BTreeSet<String>>,
+    // Local to this process; never written to the wire.
+    pub transient_headers: BTreeMap<String, String>,
 }
 
 impl ThreadContext {
@@ -84,6 +88,7 @@ impl ThreadContext {
         Ok(Self {
             request_headers: input.read_string_map()?,
             response_headers: input.read_string_set_map()?,
+            transient_headers: BTreeMap::new(),
         })
     }
 
@@ -91,6 +96,15 @@ impl ThreadContext {
         output.write_string_map(&self.request_headers);
         output.write_string_set_map(&self.response_headers);
     }
+
+    // The context a request sent on behalf of this one inherits: its request headers,
+    // but neither its response headers nor its transient headers.
+    pub fn to_outbound(&self) -> Self {
+        Self {
+            request_headers: self.request_headers.clone(),
+            ..Self::default()
+        }
+    }
 }
 
 pub mod transport_status {
@@ -320,21 +334,12 @@ impl MessageFrame {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct TaskId {
     pub node_id: String,
     pub id: Option<i64>,
 }
 
-impl Default for TaskId {
-    fn default() -> Self {
-        Self {
-            node_id: String::new(),
-            id: None,
-        }
-    }
-}
-
 impl TaskId {
     pub fn read_from(input: &mut StreamInput<'_>) -> io::Result<Self> {
         let node_id = input.read_string()?;
@@ -766,7 +771,7 @@ pub fn decode_extension_rest_request(bytes: &[u8]) -> io::Result<ExtensionRestRe
     let uri = input.read_string()?;
     let path = input.read_string()?;
     let params = input.read_string_map()?;
-    let headers = input.read_string_list_map()?;
+    let headers = HeaderMap::from(input.read_string_list_map()?);
     let media_type = if input.read_bool()? {
         Some(input.read_string()?)
     } else {
@@ -789,12 +794,35 @@ pub fn decode_extension_rest_request(bytes: &[u8]) -> io::Result<ExtensionRestRe
     ))
 }
 
-pub fn encode_extension_rest_response(response: &ExtensionRestResponse) -> Vec<u8> {
+// Streaming bodies are written straight into the payload. A body larger than
+// `max_content_length` fails with a 413 error instead of being truncated.
+pub fn encode_extension_rest_response(
+    response: &ExtensionRestResponse,
+    max_content_length: usize,
+) -> Result<Vec<u8>, ExtensionError> {
+    let too_long = || {
+        ExtensionError::content_too_long(format!(
+            "response body exceeds the maximum content length of [{max_content_length}] bytes"
+        ))
+    };
     let mut output = StreamOutput::new();
     output.write_vint(response.status.to_wire());
     output.write_string(&response.content_type);
-    output.write_byte_array(&response.content);
-    output.write_string_list_map(&response.headers);
+    match &response.stream {
+        Some(stream) => output
+            .write_byte_array_with(max_content_length, |writer| stream.write_to(writer))
+            .map_err(|error| match error.kind() {
+                io::ErrorKind::FileTooLarge => too_long(),
+                _ => ExtensionError::internal(format!("failed to write response body: {error}")),
+            })?,
+        None if response.content.len() > max_content_length => return Err(too_long()),
+        None => output.write_byte_array(&response.content),
+    }
+    output.write_vint(response.headers.len() as u32);
+    for (name, values) in response.headers.iter() {
+        output.write_string(name);
+        output.write_string_array(values);
+    }
     let consumed = response
         .consumed_params
         .iter()
@@ -802,7 +830,7 @@ pub fn encode_extension_rest_response(response: &ExtensionRestResponse) -> Vec<u
         .collect::<Vec<String>>();
     output.write_string_array(&consumed);
     output.write_bool(response.content_consumed);
-    output.into_bytes()
+    Ok(output.into_bytes())
 }
 
 fn encode_extension_identity(unique_id: &str) -> Vec<u8> {
@@ -877,6 +905,7 @@ mod tests {
     use crate::rest::{ExtensionRestResponse, RestStatus};
     use std::collections::{BTreeMap, BTreeSet};
     use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
 
     fn test_node() -> DiscoveryNode {
         DiscoveryNode {
@@ -1014,8 +1043,42 @@ mod tests {
     #[test]
     fn rest_response_payload_starts_with_status_ordinal() {
         let response = ExtensionRestResponse::text(RestStatus::Ok, "hello");
-        let bytes = super::encode_extension_rest_response(&response);
+        let bytes = super::encode_extension_rest_response(&response, usize::MAX).unwrap();
         let mut input = crate::stream::StreamInput::new(&bytes);
         assert_eq!(input.read_vint().unwrap(), 2);
     }
+
+    #[test]
+    fn rest_response_builders_write_headers_to_the_wire() {
+        let response = ExtensionRestResponse::text(RestStatus::TooManyRequests, "slow down")
+            .with_header("X-Custom", "one")
+            .with_header("x-custom", "two")
+            .with_location("/items/7")
+            .with_warning("299 OpenSearch-3.0.0 \"deprecated\"")
+            .with_retry_after(Duration::from_millis(1500));
+        assert_eq!(response.headers.get_all("x-custom"), ["one", "two"]);
+        assert_eq!(response.headers.get("location"), Some("/items/7"));
+
+        let bytes = super::encode_extension_rest_response(&response, usize::MAX).unwrap();
+        let mut input = crate::stream::StreamInput::new(&bytes);
+        input.read_vint().unwrap();
+        assert_eq!(input.read_string().unwrap(), "text/plain; charset=UTF-8");
+        assert_eq!(input.read_byte_array().unwrap(), b"slow down");
+        let headers = input.read_string_list_map().unwrap();
+        assert_eq!(
+            headers,
+            BTreeMap::from([
+                ("Location".to_string(), vec!["/items/7".to_string()]),
+                ("Retry-After".to_string(), vec!["2".to_string()]),
+                (
+                    "Warning".to_string(),
+                    vec!["299 OpenSearch-3.0.0 \"deprecated\"".to_string()]
+                ),
+                (
+                    "X-Custom".to_string(),
+                    vec!["one".to_string(), "two".to_string()]
+                ),
+            ])
+        );
+    }
 }
@@ -0,0 +1,289 @@
+use crate::json::JsonValue;
+use crate::{cbor, smile, yaml};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XContentError {
+    pub message: String,
+}
+
+impl XContentError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for XContentError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for XContentError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MediaType {
+    pub type_name: String,
+    pub subtype: String,
+    pub params: BTreeMap<String, String>,
+}
+
+impl MediaType {
+    pub fn parse(value: &str) -> Result<Self, XContentError> {
+        let invalid = || XContentError::new(format!("invalid media type [{value}]"));
+        let mut parts = value.split(';');
+        let essence = parts.next().unwrap_or_default().trim();
+        let (type_name, subtype) = essence.split_once('/').ok_or_else(invalid)?;
+        if !is_token(type_name) || !is_token(subtype) {
+            return Err(invalid());
+        }
+
+        let mut params = BTreeMap::new();
+        for param in parts {
+            let param = param.trim();
+            if param.is_empty() {
+                continue;
+            }
+            let (name, value) = param.split_once('=').ok_or_else(invalid)?;
+            let name = name.trim();
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            if !is_token(name) {
+                return Err(invalid());
+            }
+            params.insert(name.to_ascii_lowercase(), value.to_string());
+        }
+
+        Ok(Self {
+            type_name: type_name.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params,
+        })
+    }
+
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_name, self.subtype)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    pub fn compatible_with(&self) -> Option<u32> {
+        self.param("compatible-with")?.parse().ok()
+    }
+
+    pub fn quality(&self) -> f32 {
+        self.param("q")
+            .and_then(|quality| quality.parse().ok())
+            .unwrap_or(1.0)
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.subtype == "*"
+    }
+
+    pub fn xcontent_type(&self) -> Option<XContentType> {
+        XContentType::ALL.into_iter().find(|xcontent| {
+            xcontent.essences().contains(&self.essence().as_str())
+                || (self.type_name == "application"
+                    && self.subtype.ends_with(&format!("+{}", xcontent.format())))
+        })
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}/{}", self.type_name, self.subtype)?;
+        for (name, value) in &self.params {
+            write!(formatter, "; {name}={value}")?;
+        }
+        Ok(())
+    }
+}
+
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$&-^_.+*".contains(&byte))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum XContentType {
+    Json,
+    Yaml,
+    Cbor,
+    Smile,
+}
+
+impl XContentType {
+    pub const ALL: [XContentType; 4] = [Self::Json, Self::Yaml, Self::Cbor, Self::Smile];
+
+    pub fn from_format(format: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|xcontent| xcontent.format().eq_ignore_ascii_case(format))
+    }
+
+    pub fn format(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Cbor => "cbor",
+            Self::Smile => "smile",
+        }
+    }
+
+    pub fn media_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json; charset=UTF-8",
+            Self::Yaml => "application/yaml",
+            Self::Cbor => "application/cbor",
+            Self::Smile => "application/smile",
+        }
+    }
+
+    fn essences(self) -> &'static [&'static str] {
+        match self {
+            Self::Json => &["application/json", "text/json"],
+            Self::Yaml => &["application/yaml", "application/x-yaml", "text/yaml"],
+            Self::Cbor => &["application/cbor"],
+            Self::Smile => &["application/smile", "application/x-jackson-smile"],
+        }
+    }
+
+    pub fn decode(self, content: &[u8]) -> Result<JsonValue, XContentError> {
+        match self {
+            Self::Json => JsonValue::parse_bytes(content)
+                .map_err(|error| XContentError::new(error.to_string())),
+            Self::Yaml => yaml::decode(content),
+            Self::Cbor => cbor::decode(content),
+            Self::Smile => smile::decode(content),
+        }
+    }
+
+    pub fn encode(self, value: &JsonValue) -> Vec<u8> {
+        match self {
+            Self::Json => value.to_bytes(),
+            Self::Yaml => yaml::encode(value).into_bytes(),
+            Self::Cbor => cbor::encode(value),
+            Self::Smile => smile::encode(value),
+        }
+    }
+}
+
+// Picks the response format the way OpenSearch does: `?format=` first, then the
+// most preferred supported `Accept` entry, then the request body's own format.
+pub fn negotiate(
+    format: Option<&str>,
+    accept: Option<&str>,
+    content_type: Option<XContentType>,
+) -> Result<XContentType, XContentError> {
+    if let Some(format) = format {
+        return XContentType::from_format(format)
+            .ok_or_else(|| XContentError::new(format!("unsupported format [{format}]")));
+    }
+
+    let fallback = content_type.unwrap_or(XContentType::Json);
+    let Some(accept) = accept else {
+        return Ok(fallback);
+    };
+
+    let mut accepted = accept
+        .split(',')
+        .filter_map(|entry| MediaType::parse(entry).ok())
+        .filter(|media_type| media_type.quality() > 0.0)
+        .collect::<Vec<_>>();
+    accepted.sort_by(|left, right| right.quality().total_cmp(&left.quality()));
+    Ok(accepted
+        .iter()
+        .find_map(|media_type| {
+            if media_type.is_wildcard() {
+                Some(fallback)
+            } else {
+                media_type.xcontent_type()
+            }
+        })
+        .unwrap_or(fallback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{negotiate, MediaType, XContentType};
+    use crate::json::JsonValue;
+
+    #[test]
+    fn media_types_parse_parameters_and_vendor_suffixes() {
+        let media_type = MediaType::parse(
+            "Application/VND.OpenSearch+JSON; charset=\"utf-8\"; compatible-with=7",
+        )
+        .unwrap();
+        assert_eq!(media_type.essence(), "application/vnd.opensearch+json");
+        assert_eq!(media_type.charset(), Some("utf-8"));
+        assert_eq!(media_type.compatible_with(), Some(7));
+        assert_eq!(media_type.xcontent_type(), Some(XContentType::Json));
+        assert_eq!(
+            MediaType::parse("application/x-yaml")
+                .unwrap()
+                .xcontent_type(),
+            Some(XContentType::Yaml)
+        );
+        assert_eq!(
+            MediaType::parse("text/plain").unwrap().xcontent_type(),
+            None
+        );
+        assert!(MediaType::parse("json").is_err());
+        assert!(MediaType::parse("application/json; charset").is_err());
+    }
+
+    #[test]
+    fn negotiation_prefers_format_then_accept_then_body() {
+        assert_eq!(
+            negotiate(Some("yaml"), Some("application/cbor"), None),
+            Ok(XContentType::Yaml)
+        );
+        assert!(negotiate(Some("xml"), None, None).is_err());
+        assert_eq!(
+            negotiate(
+                None,
+                Some("application/json;q=0.5, application/smile"),
+                None
+            ),
+            Ok(XContentType::Smile)
+        );
+        assert_eq!(
+            negotiate(None, Some("*/*"), Some(XContentType::Cbor)),
+            Ok(XContentType::Cbor)
+        );
+        assert_eq!(
+            negotiate(None, Some("text/html"), None),
+            Ok(XContentType::Json)
+        );
+    }
+
+    #[test]
+    fn every_format_round_trips_the_value_model() {
+        let document = JsonValue::parse(
+            r#"{"index":"logs","count":3,"ratio":1.5,"tags":["a","b"],"nested":{"ok":true,"none":null},"empty":{}}"#,
+        )
+        .unwrap();
+        for xcontent in XContentType::ALL {
+            assert_eq!(
+                xcontent.decode(&xcontent.encode(&document)),
+                Ok(document.clone()),
+                "{xcontent:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,904 @@
+use crate::json::JsonValue;
+use crate::xcontent::XContentError;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+// Block and flow collections, quoted and plain scalars, and literal/folded block
+// scalars. Anchors, aliases, tags and multi-document streams are rejected.
+const MAX_DEPTH: usize = 128;
+
+pub fn decode(input: &[u8]) -> Result<JsonValue, XContentError> {
+    let input = std::str::from_utf8(input)
+        .map_err(|_| XContentError::new("YAML input is not valid UTF-8"))?;
+    let mut lines = Vec::new();
+    let mut raw_lines = input.lines().enumerate();
+    for (index, raw) in raw_lines.by_ref() {
+        // `...` ends the document; only blank lines and comments may follow it.
+        if raw.trim_end() == "..." {
+            break;
+        }
+        let content = raw.trim_start_matches(' ');
+        let indent = raw.len() - content.len();
+        if content.starts_with('\t') {
+            return Err(line_error(
+                index + 1,
+                "tabs are not allowed for indentation",
+            ));
+        }
+        lines.push(Line {
+            number: index + 1,
+            indent,
+            raw: content.trim_end(),
+        });
+    }
+
+    if let Some((index, _)) = raw_lines.find(|(_, raw)| {
+        let raw = raw.trim();
+        !raw.is_empty() && !raw.starts_with('#')
+    }) {
+        return Err(line_error(
+            index + 1,
+            "unexpected content after the document end marker",
+        ));
+    }
+
+    let mut parser = Parser { lines, index: 0 };
+    parser.skip_document_markers()?;
+    let Some(first) = parser.current() else {
+        return Ok(JsonValue::Null);
+    };
+    let value = parser.parse_block(first.indent, 0)?;
+    parser.skip_ignorable();
+    if let Some(line) = parser.current() {
+        return Err(line_error(line.number, "unexpected content"));
+    }
+    Ok(value)
+}
+
+pub fn encode(value: &JsonValue) -> String {
+    let mut output = String::from("---");
+    match value {
+        JsonValue::Object(entries) if !entries.is_empty() => {
+            output.push('\n');
+            write_mapping(entries, 0, &mut output);
+        }
+        JsonValue::Array(values) if !values.is_empty() => {
+            output.push('\n');
+            write_sequence(values, 0, &mut output);
+        }
+        scalar => {
+            output.push(' ');
+            write_scalar(scalar, &mut output);
+            output.push('\n');
+        }
+    }
+    output
+}
+
+fn line_error(line: usize, message: &str) -> XContentError {
+    XContentError::new(format!("{message} at line {line}"))
+}
+
+#[derive(Clone, Copy)]
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    raw: &'a str,
+}
+
+impl<'a> Line<'a> {
+    fn text(&self) -> &'a str {
+        strip_comment(self.raw).trim_end()
+    }
+
+    fn is_ignorable(&self) -> bool {
+        let text = self.text();
+        text.is_empty() || text.starts_with("%")
+    }
+
+    fn is_sequence_item(&self) -> bool {
+        let text = self.text();
+        text == "-" || text.starts_with("- ")
+    }
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn current(&self) -> Option<Line<'a>> {
+        self.lines.get(self.index).copied()
+    }
+
+    fn skip_ignorable(&mut self) {
+        while self.current().is_some_and(|line| line.is_ignorable()) {
+            self.index += 1;
+        }
+    }
+
+    fn skip_document_markers(&mut self) -> Result<(), XContentError> {
+        self.skip_ignorable();
+        if let Some(line) = self.current() {
+            let text = line.text();
+            if text == "---" {
+                self.index += 1;
+            } else if let Some(rest) = text.strip_prefix("--- ") {
+                // An inline document such as `--- {}` replaces the marker line.
+                self.lines[self.index] = Line {
+                    number: line.number,
+                    indent: line.indent + 4,
+                    raw: rest,
+                };
+            }
+        }
+        self.skip_ignorable();
+        if self.lines[self.index..]
+            .iter()
+            .any(|line| line.text() == "---")
+        {
+            return Err(XContentError::new(
+                "multiple YAML documents are not supported",
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_block(&mut self, indent: usize, depth: usize) -> Result<JsonValue, XContentError> {
+        if depth > MAX_DEPTH {
+            return Err(XContentError::new("YAML input is nested too deeply"));
+        }
+        self.skip_ignorable();
+        let Some(line) = self.current() else {
+            return Ok(JsonValue::Null);
+        };
+
+        if line.is_sequence_item() {
+            self.parse_sequence(indent, depth)
+        } else if mapping_colon(line.text()).is_some() {
+            self.parse_mapping(indent, depth)
+        } else {
+            self.index += 1;
+            self.parse_inline(line, line.text(), depth)
+        }
+    }
+
+    fn parse_sequence(&mut self, indent: usize, depth: usize) -> Result<JsonValue, XContentError> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_ignorable();
+            let Some(line) = self.current() else {
+                break;
+            };
+            if line.indent < indent || (line.indent == indent && !line.is_sequence_item()) {
+                break;
+            }
+            if line.indent > indent {
+                return Err(line_error(
+                    line.number,
+                    "bad indentation of a sequence entry",
+                ));
+            }
+
+            let text = line.text();
+            let rest = text[1..].trim_start();
+            if rest.is_empty() {
+                self.index += 1;
+                values.push(self.parse_nested(indent, false, depth)?);
+                continue;
+            }
+
+            // `- key: value` opens a mapping indented to where its first key starts.
+            let offset = text.len() - rest.len();
+            let nested = Line {
+                number: line.number,
+                indent: indent + offset,
+                raw: &line.raw[offset..],
+            };
+            if nested.is_sequence_item() || mapping_colon(rest).is_some() {
+                self.lines[self.index] = nested;
+                values.push(self.parse_block(nested.indent, depth + 1)?);
+            } else {
+                self.index += 1;
+                values.push(self.parse_inline(nested, rest, depth + 1)?);
+            }
+        }
+        Ok(JsonValue::Array(values))
+    }
+
+    fn parse_mapping(&mut self, indent: usize, depth: usize) -> Result<JsonValue, XContentError> {
+        let mut entries = BTreeMap::new();
+        loop {
+            self.skip_ignorable();
+            let Some(line) = self.current() else {
+                break;
+            };
+            if line.indent < indent || (line.indent == indent && line.is_sequence_item()) {
+                break;
+            }
+            let text = line.text();
+            let colon = match mapping_colon(text) {
+                Some(colon) if line.indent == indent => colon,
+                _ => {
+                    return Err(line_error(
+                        line.number,
+                        "bad indentation of a mapping entry",
+                    ))
+                }
+            };
+
+            let key = match parse_scalar(text[..colon].trim(), line.number)? {
+                JsonValue::String(key) => key,
+                JsonValue::Null => String::new(),
+                scalar => scalar.to_string(),
+            };
+            if entries.contains_key(&key) {
+                return Err(line_error(
+                    line.number,
+                    &format!("duplicate mapping key [{key}]"),
+                ));
+            }
+            let rest = text[colon + 1..].trim();
+            // `a: b: c` is an error in YAML, not the string "b: c".
+            if mapping_colon(rest).is_some() {
+                return Err(line_error(
+                    line.number,
+                    "mapping values are not allowed here",
+                ));
+            }
+            self.index += 1;
+            let value = if rest.is_empty() {
+                self.parse_nested(indent, true, depth)?
+            } else if rest.starts_with('|') || rest.starts_with('>') {
+                self.parse_block_scalar(indent, rest, line.number)?
+            } else {
+                self.parse_inline(line, rest, depth + 1)?
+            };
+            entries.insert(key, value);
+        }
+        Ok(JsonValue::Object(entries))
+    }
+
+    // The value of `key:` or `-` on its own line: a deeper block, a sequence at the
+    // same indent (only for mapping values), or null.
+    fn parse_nested(
+        &mut self,
+        indent: usize,
+        mapping_value: bool,
+        depth: usize,
+    ) -> Result<JsonValue, XContentError> {
+        self.skip_ignorable();
+        match self.current() {
+            Some(next) if next.indent > indent => self.parse_block(next.indent, depth + 1),
+            Some(next) if mapping_value && next.indent == indent && next.is_sequence_item() => {
+                self.parse_sequence(indent, depth + 1)
+            }
+            _ => Ok(JsonValue::Null),
+        }
+    }
+
+    fn parse_inline(
+        &mut self,
+        line: Line<'a>,
+        text: &str,
+        depth: usize,
+    ) -> Result<JsonValue, XContentError> {
+        if !(text.starts_with('[') || text.starts_with('{')) {
+            return parse_scalar(text, line.number);
+        }
+
+        // Flow collections may continue over the following lines.
+        let mut flow = text.to_string();
+        while !flow_is_balanced(&flow) {
+            let Some(next) = self.current() else {
+                return Err(line_error(line.number, "unterminated flow collection"));
+            };
+            flow.push(' ');
+            flow.push_str(next.text().trim());
+            self.index += 1;
+        }
+        let mut parser = FlowParser {
+            chars: flow.chars().collect(),
+            position: 0,
+            line: line.number,
+        };
+        let value = parser.parse_value(depth)?;
+        parser.skip_whitespace();
+        if parser.position < parser.chars.len() {
+            return Err(line_error(
+                line.number,
+                "unexpected characters after flow collection",
+            ));
+        }
+        Ok(value)
+    }
+
+    fn parse_block_scalar(
+        &mut self,
+        indent: usize,
+        header: &str,
+        number: usize,
+    ) -> Result<JsonValue, XContentError> {
+        let folded = header.starts_with('>');
+        let chomping = header[1..].trim();
+        if !matches!(chomping, "" | "-" | "+") {
+            return Err(line_error(number, "unsupported block scalar header"));
+        }
+
+        let mut content = Vec::new();
+        let mut content_indent = None;
+        while let Some(line) = self.current() {
+            if line.raw.is_empty() {
+                content.push(String::new());
+                self.index += 1;
+                continue;
+            }
+            let block_indent = *content_indent.get_or_insert(line.indent);
+            if line.indent <= indent || line.indent < block_indent {
+                break;
+            }
+            content.push(format!(
+                "{}{}",
+                " ".repeat(line.indent - block_indent),
+                line.raw
+            ));
+            self.index += 1;
+        }
+
+        let trailing = content
+            .iter()
+            .rev()
+            .take_while(|line| line.is_empty())
+            .count();
+        let body = &content[..content.len() - trailing];
+        let mut text = if folded {
+            fold_lines(body)
+        } else {
+            body.join("\n")
+        };
+        match chomping {
+            "-" => {}
+            "+" => text.push_str(&"\n".repeat(trailing + 1)),
+            _ if !body.is_empty() => text.push('\n'),
+            _ => {}
+        }
+        Ok(JsonValue::String(text))
+    }
+}
+
+fn fold_lines(lines: &[String]) -> String {
+    let mut text = String::new();
+    let mut previous_blank = true;
+    for line in lines {
+        if line.is_empty() {
+            text.push('\n');
+            previous_blank = true;
+            continue;
+        }
+        if !previous_blank {
+            text.push(' ');
+        }
+        text.push_str(line);
+        previous_blank = false;
+    }
+    text
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (index, character) in text.char_indices() {
+        match (quote, character) {
+            (None, '#') if previous == ' ' => return &text[..index],
+            (None, '"' | '\'') if matches!(previous, ' ' | '[' | '{' | ',' | ':' | '-') => {
+                quote = Some(character)
+            }
+            (Some('"'), '\\') => {}
+            (Some(open), _) if open == character && previous != '\\' => quote = None,
+            _ => {}
+        }
+        previous = character;
+    }
+    text
+}
+
+// Position of the `:` that separates a block mapping key from its value.
+fn mapping_colon(text: &str) -> Option<usize> {
+    if text.starts_with('[') || text.starts_with('{') {
+        return None;
+    }
+    let mut quote = None;
+    let bytes = text.as_bytes();
+    for (index, byte) in bytes.iter().enumerate() {
+        match (quote, byte) {
+            (None, b'"' | b'\'') if index == 0 => quote = Some(*byte),
+            (Some(b'"'), b'\\') => {}
+            (Some(open), _) if open == *byte && (index == 0 || bytes[index - 1] != b'\\') => {
+                quote = None
+            }
+            (None, b':') if matches!(bytes.get(index + 1), None | Some(b' ' | b'\t')) => {
+                return Some(index)
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn flow_is_balanced(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut previous = ' ';
+    for character in text.chars() {
+        match (quote, character) {
+            (None, '"' | '\'') => quote = Some(character),
+            (Some(open), _) if open == character && previous != '\\' => quote = None,
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            _ => {}
+        }
+        previous = character;
+    }
+    depth <= 0 && quote.is_none()
+}
+
+fn parse_scalar(text: &str, line: usize) -> Result<JsonValue, XContentError> {
+    if text.starts_with('"') {
+        return parse_double_quoted(text, line).map(JsonValue::String);
+    }
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner
+            .strip_suffix('\'')
+            .ok_or_else(|| line_error(line, "unterminated single-quoted string"))?;
+        return Ok(JsonValue::String(inner.replace("''", "'")));
+    }
+    if text.starts_with(['&', '*', '!', '|', '>', '@', '`']) {
+        return Err(line_error(
+            line,
+            "anchors, aliases, tags and reserved indicators are not supported",
+        ));
+    }
+    Ok(resolve_plain(text))
+}
+
+fn parse_double_quoted(text: &str, line: usize) -> Result<String, XContentError> {
+    let unterminated = || line_error(line, "unterminated double-quoted string");
+    let inner = text
+        .strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .filter(|_| text.len() >= 2)
+        .ok_or_else(unterminated)?;
+
+    let mut output = String::new();
+    let mut characters = inner.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            output.push(character);
+            continue;
+        }
+        let escaped = characters.next().ok_or_else(unterminated)?;
+        let hex_digits = match escaped {
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            _ => 0,
+        };
+        if hex_digits > 0 {
+            let code = characters.by_ref().take(hex_digits).collect::<String>();
+            let decoded = u32::from_str_radix(&code, 16)
+                .ok()
+                .filter(|_| code.len() == hex_digits)
+                .and_then(char::from_u32)
+                .ok_or_else(|| line_error(line, "invalid escape sequence"))?;
+            output.push(decoded);
+            continue;
+        }
+        output.push(match escaped {
+            '0' => '\0',
+            'a' => '\u{7}',
+            'b' => '\u{8}',
+            't' | '\t' => '\t',
+            'n' => '\n',
+            'v' => '\u{b}',
+            'f' => '\u{c}',
+            'r' => '\r',
+            'e' => '\u{1b}',
+            ' ' => ' ',
+            '"' => '"',
+            '/' => '/',
+            '\\' => '\\',
+            'N' => '\u{85}',
+            '_' => '\u{a0}',
+            _ => return Err(line_error(line, "invalid escape sequence")),
+        });
+    }
+    Ok(output)
+}
+
+// YAML 1.2 core schema resolution for plain scalars.
+fn resolve_plain(text: &str) -> JsonValue {
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return JsonValue::Null,
+        "true" | "True" | "TRUE" => return JsonValue::Bool(true),
+        "false" | "False" | "FALSE" => return JsonValue::Bool(false),
+        ".inf" | ".Inf" | ".INF" | "+.inf" | "+.Inf" | "+.INF" => {
+            return JsonValue::Float(f64::INFINITY)
+        }
+        "-.inf" | "-.Inf" | "-.INF" => return JsonValue::Float(f64::NEG_INFINITY),
+        ".nan" | ".NaN" | ".NAN" => return JsonValue::Float(f64::NAN),
+        _ => {}
+    }
+
+    let unsigned = text.trim_start_matches(['-', '+']);
+    let radix_value = if let Some(hex) = text.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(octal) = text.strip_prefix("0o") {
+        i64::from_str_radix(octal, 8).ok()
+    } else {
+        None
+    };
+    if let Some(value) = radix_value {
+        return JsonValue::Integer(value);
+    }
+    if text.len() - unsigned.len() <= 1
+        && !unsigned.is_empty()
+        && unsigned.bytes().all(|byte| byte.is_ascii_digit())
+    {
+        return text
+            .parse()
+            .map(JsonValue::Integer)
+            .unwrap_or_else(|_| JsonValue::Float(text.parse().unwrap_or(f64::NAN)));
+    }
+    if looks_like_float(unsigned) && text.len() - unsigned.len() <= 1 {
+        if let Ok(value) = text.parse::<f64>() {
+            return JsonValue::Float(value);
+        }
+    }
+    JsonValue::String(text.to_string())
+}
+
+fn looks_like_float(text: &str) -> bool {
+    let mantissa = text.split(['e', 'E']).next().unwrap_or_default();
+    !mantissa.is_empty()
+        && mantissa != "."
+        && mantissa
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || byte == b'.')
+        && mantissa.bytes().filter(|byte| *byte == b'.').count() <= 1
+}
+
+struct FlowParser {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+}
+
+impl FlowParser {
+    fn error(&self, message: &str) -> XContentError {
+        line_error(self.line, message)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<JsonValue, XContentError> {
+        if depth > MAX_DEPTH {
+            return Err(XContentError::new("YAML input is nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some('[') => self.parse_sequence(depth),
+            Some('{') => self.parse_mapping(depth),
+            _ => self.parse_flow_scalar(false),
+        }
+    }
+
+    fn parse_sequence(&mut self, depth: usize) -> Result<JsonValue, XContentError> {
+        self.position += 1;
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Array(values));
+                }
+                None => return Err(self.error("unterminated flow sequence")),
+                _ => {}
+            }
+            values.push(self.parse_value(depth + 1)?);
+            self.finish_entry(']')?;
+        }
+    }
+
+    fn parse_mapping(&mut self, depth: usize) -> Result<JsonValue, XContentError> {
+        self.position += 1;
+        let mut entries = BTreeMap::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.position += 1;
+                    return Ok(JsonValue::Object(entries));
+                }
+                None => return Err(self.error("unterminated flow mapping")),
+                _ => {}
+            }
+            let key = match self.parse_flow_scalar(true)? {
+                JsonValue::String(key) => key,
+                JsonValue::Null => String::new(),
+                scalar => scalar.to_string(),
+            };
+            if entries.contains_key(&key) {
+                return Err(self.error(&format!("duplicate mapping key [{key}]")));
+            }
+            self.skip_whitespace();
+            let value = if self.peek() == Some(':') {
+                self.position += 1;
+                self.skip_whitespace();
+                if matches!(self.peek(), Some(',' | '}')) {
+                    JsonValue::Null
+                } else {
+                    self.parse_value(depth + 1)?
+                }
+            } else {
+                JsonValue::Null
+            };
+            entries.insert(key, value);
+            self.finish_entry('}')?;
+        }
+    }
+
+    fn finish_entry(&mut self, close: char) -> Result<(), XContentError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(',') => {
+                self.position += 1;
+                Ok(())
+            }
+            Some(character) if character == close => Ok(()),
+            _ => Err(self.error("expected ',' in flow collection")),
+        }
+    }
+
+    fn parse_flow_scalar(&mut self, key: bool) -> Result<JsonValue, XContentError> {
+        let start = self.position;
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.position += 1;
+                while let Some(character) = self.peek() {
+                    self.position += 1;
+                    if character == '\\' && quote == '"' {
+                        self.position += 1;
+                    } else if character == quote {
+                        if quote == '\'' && self.peek() == Some('\'') {
+                            self.position += 1;
+                            continue;
+                        }
+                        break;
+                    }
+                }
+            }
+            _ => {
+                while let Some(character) = self.peek() {
+                    let next = self.chars.get(self.position + 1).copied();
+                    let ends_key = character == ':'
+                        && (key
+                            || next
+                                .is_none_or(|next| next.is_whitespace() || ",]}".contains(next)));
+                    if matches!(character, ',' | ']' | '}') || ends_key {
+                        break;
+                    }
+                    self.position += 1;
+                }
+            }
+        }
+        let text = self.chars[start..self.position].iter().collect::<String>();
+        parse_scalar(text.trim(), self.line)
+    }
+}
+
+fn write_mapping(entries: &BTreeMap<String, JsonValue>, indent: usize, output: &mut String) {
+    for (index, (key, value)) in entries.iter().enumerate() {
+        if index > 0 {
+            push_indent(indent, output);
+        }
+        write_key(key, output);
+        output.push(':');
+        match value {
+            JsonValue::Object(entries) if !entries.is_empty() => {
+                output.push('\n');
+                push_indent(indent + 2, output);
+                write_mapping(entries, indent + 2, output);
+            }
+            JsonValue::Array(values) if !values.is_empty() => {
+                output.push('\n');
+                push_indent(indent, output);
+                write_sequence(values, indent, output);
+            }
+            scalar => {
+                output.push(' ');
+                write_scalar(scalar, output);
+                output.push('\n');
+            }
+        }
+    }
+}
+
+fn write_sequence(values: &[JsonValue], indent: usize, output: &mut String) {
+    for (index, value) in values.iter().enumerate() {
+        if index > 0 {
+            push_indent(indent, output);
+        }
+        output.push_str("- ");
+        match value {
+            JsonValue::Object(entries) if !entries.is_empty() => {
+                write_mapping(entries, indent + 2, output)
+            }
+            JsonValue::Array(values) if !values.is_empty() => {
+                write_sequence(values, indent + 2, output)
+            }
+            scalar => {
+                write_scalar(scalar, output);
+                output.push('\n');
+            }
+        }
+    }
+}
+
+fn push_indent(indent: usize, output: &mut String) {
+    output.push_str(&" ".repeat(indent));
+}
+
+fn write_key(key: &str, output: &mut String) {
+    let plain = !key.is_empty()
+        && key
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"_-./".contains(&byte))
+        && !key.starts_with(['-', '.'])
+        && matches!(resolve_plain(key), JsonValue::String(_));
+    if plain {
+        output.push_str(key);
+    } else {
+        write_quoted(key, output);
+    }
+}
+
+fn write_scalar(value: &JsonValue, output: &mut String) {
+    match value {
+        JsonValue::Float(value) if value.is_nan() => output.push_str(".NaN"),
+        JsonValue::Float(value) if value.is_infinite() => {
+            output.push_str(if *value > 0.0 { ".inf" } else { "-.inf" })
+        }
+        JsonValue::String(value) => write_quoted(value, output),
+        JsonValue::Array(_) => output.push_str("[]"),
+        JsonValue::Object(_) => output.push_str("{}"),
+        scalar => output.push_str(&scalar.to_string()),
+    }
+}
+
+fn write_quoted(value: &str, output: &mut String) {
+    output.push('"');
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            character if character.is_control() => {
+                let _ = write!(output, "\\u{:04x}", character as u32);
+            }
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::json::JsonValue;
+
+    #[test]
+    fn decodes_block_and_flow_documents() {
+        let document = r#"---
+# index settings
+index: logs-2024   # trailing comment
+shards: 3
+ratio: 0.5
+enabled: yes
+missing: ~
+tags: [a, "b c", 'it''s']
+inline: {size: 10, sort: {field: desc}}
+hosts:
+- name: one
+  port: 9200
+- name: two
+  roles:
+    - data
+    - ingest
+query:
+  match_all: {}
+script: |
+  line one
+  line two
+summary: >-
+  folded
+  text
+"#;
+        let expected = JsonValue::parse(
+            r#"{"index":"logs-2024","shards":3,"ratio":0.5,"enabled":"yes","missing":null,
+                "tags":["a","b c","it's"],"inline":{"size":10,"sort":{"field":"desc"}},
+                "hosts":[{"name":"one","port":9200},{"name":"two","roles":["data","ingest"]}],
+                "query":{"match_all":{}},"script":"line one\nline two\n","summary":"folded text"}"#,
+        )
+        .unwrap();
+        assert_eq!(decode(document.as_bytes()).unwrap(), expected);
+
+        assert_eq!(
+            decode(b"a:\n  b: 1\n c: 2").unwrap_err().to_string(),
+            "bad indentation of a mapping entry at line 3"
+        );
+        assert!(decode(b"a: &anchor 1").is_err());
+        assert!(decode(b"a: 1\n---\nb: 2").is_err());
+    }
+
+    #[test]
+    fn rejects_nested_inline_mappings_and_duplicate_keys() {
+        assert_eq!(
+            decode(b"a: b: c").unwrap_err().to_string(),
+            "mapping values are not allowed here at line 1"
+        );
+        assert!(decode(b"- a: b: c").is_err());
+        assert_eq!(
+            decode(b"url: http://localhost:9200").unwrap(),
+            JsonValue::parse(r#"{"url":"http://localhost:9200"}"#).unwrap()
+        );
+        assert_eq!(
+            decode(b"a: 'b: c'").unwrap(),
+            JsonValue::parse(r#"{"a":"b: c"}"#).unwrap()
+        );
+
+        assert_eq!(
+            decode(b"a: 1\nb: 2\na: 3").unwrap_err().to_string(),
+            "duplicate mapping key [a] at line 3"
+        );
+        assert!(decode(b"a: {b: 1, b: 2}").is_err());
+    }
+
+    #[test]
+    fn accepts_document_end_markers_and_tabs_after_colons() {
+        let expected = JsonValue::parse(r#"{"a":1,"b":{"c":"d"}}"#).unwrap();
+        assert_eq!(
+            decode(b"a:\t1\nb:\n  c:\td\n...\n# done\n").unwrap(),
+            expected
+        );
+        assert_eq!(
+            decode(b"a: 1\n...\nb: 2").unwrap_err().to_string(),
+            "unexpected content after the document end marker at line 3"
+        );
+    }
+
+    #[test]
+    fn encodes_in_jackson_block_style() {
+        let value = JsonValue::parse(
+            r#"{"acknowledged":true,"indices":["a",{"name":"b","size":1}],"nested":{"key with space":"v\"q"},"empty":[]}"#,
+        )
+        .unwrap();
+        let encoded = encode(&value);
+        assert_eq!(
+            encoded,
+            "---\nacknowledged: true\nempty: []\nindices:\n- \"a\"\n- name: \"b\"\n  size: 1\nnested:\n  \"key with space\": \"v\\\"q\"\n"
+        );
+        assert_eq!(decode(encoded.as_bytes()).unwrap(), value);
+    }
+}